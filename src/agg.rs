@@ -0,0 +1,217 @@
+use std::{collections::HashMap, hash::Hash, mem};
+
+use rayon::prelude::*;
+
+use crate::{shutdown::Shutdown, LineSplitParse};
+
+/// Merge `b` into `a`, combining values for keys in both with `merge`.
+fn merge_maps<K, V, M>(mut a: HashMap<K, V>, mut b: HashMap<K, V>, merge: M) -> HashMap<K, V>
+where
+    K: Hash + Eq,
+    M: Fn(V, V) -> V,
+{
+    if a.len() < b.len() {
+        mem::swap(&mut a, &mut b);
+    }
+    for (key, bv) in b {
+        let v = match a.remove(&key) {
+            Some(av) => merge(av, bv),
+            None => bv,
+        };
+        a.insert(key, v);
+    }
+    a
+}
+
+/// Find the `k` most frequent keys in a stream of line-aligned chunks.
+///
+/// `key` extracts a key from each non-empty line, or returns `None` to skip
+/// it. Chunks are counted in parallel into per-chunk hash maps, which are then
+/// merged. Returns up to `k` keys with their counts, most frequent first, with
+/// ties broken by key order. The first error from the chunk stream is returned
+/// instead. If `key` panics, no more chunks are read and the panic is
+/// propagated.
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// let log = &b"10.0.0.1 GET /\n10.0.0.2 GET /\n10.0.0.1 GET /favicon.ico\n"[..];
+/// let top = linechunks::top_k(linechunks::LineChunks::new(8192, log), 1, |line| {
+///     line.split(|&c| c == b' ').next().map(<[u8]>::to_vec)
+/// })?;
+/// assert_eq!(top, vec![(b"10.0.0.1".to_vec(), 2)]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn top_k<I, E, K, F>(chunks: I, k: usize, key: F) -> Result<Vec<(K, u64)>, E>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>> + Send,
+    E: Send,
+    K: Hash + Ord + Send,
+    F: Fn(&[u8]) -> Option<K> + Sync,
+{
+    let shutdown = Shutdown::default();
+    let counts = shutdown
+        .source(chunks)
+        .par_bridge()
+        .try_fold(HashMap::new, |mut counts, chunk| {
+            let _guard = shutdown.guard();
+            for key in LineSplitParse::new(chunk?, &key).flatten() {
+                *counts.entry(key).or_insert(0) += 1;
+            }
+            Ok(counts)
+        })
+        .try_reduce(HashMap::new, |a, b| Ok(merge_maps(a, b, |a, b| a + b)))?;
+
+    let mut counts: Vec<(K, u64)> = counts.into_iter().collect();
+    let order = |a: &(K, u64), b: &(K, u64)| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0));
+
+    if k < counts.len() {
+        counts.select_nth_unstable_by(k, order);
+        counts.truncate(k);
+    }
+    counts.sort_unstable_by(order);
+
+    Ok(counts)
+}
+
+/// Aggregate the lines of a stream of line-aligned chunks by key.
+///
+/// `key` extracts a key from each non-empty line, or returns `None` to skip
+/// it. Each line is then folded into its key's value with `fold`, starting
+/// from `V::default()`. Chunks are aggregated in parallel into per-chunk hash
+/// maps, and values for the same key from different chunks are combined with
+/// `merge`. Chunks are processed in no particular order, so `merge` should be
+/// associative and commutative. The first error from the chunk stream is
+/// returned instead. If `key` or `fold` panics, no more chunks are read and
+/// the panic is propagated.
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// let log = &b"alice 3\nbob 4\nalice 5\n"[..];
+/// let totals = linechunks::group_by_key(
+///     linechunks::LineChunks::new(8192, log),
+///     |line| line.split(|&c| c == b' ').next().map(<[u8]>::to_vec),
+///     |total: &mut u64, line| {
+///         let n = line.rsplit(|&c| c == b' ').next().unwrap();
+///         *total += std::str::from_utf8(n).unwrap().parse::<u64>().unwrap();
+///     },
+///     |a, b| a + b,
+/// )?;
+/// assert_eq!(totals[&b"alice"[..]], 8);
+/// # Ok(())
+/// # }
+/// ```
+pub fn group_by_key<I, E, K, V, KF, F, M>(
+    chunks: I,
+    key: KF,
+    fold: F,
+    merge: M,
+) -> Result<HashMap<K, V>, E>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>> + Send,
+    E: Send,
+    K: Hash + Eq + Send,
+    V: Default + Send,
+    KF: Fn(&[u8]) -> Option<K> + Sync,
+    F: Fn(&mut V, &[u8]) + Sync,
+    M: Fn(V, V) -> V + Sync,
+{
+    let shutdown = Shutdown::default();
+    shutdown
+        .source(chunks)
+        .par_bridge()
+        .try_fold(HashMap::new, |mut groups, chunk| {
+            let _guard = shutdown.guard();
+            let add = |line: &[u8]| {
+                if let Some(key) = key(line) {
+                    fold(groups.entry(key).or_default(), line);
+                }
+            };
+            LineSplitParse::new(chunk?, add).for_each(drop);
+            Ok(groups)
+        })
+        .try_reduce(HashMap::new, |a, b| Ok(merge_maps(a, b, &merge)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        iter,
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_top_k() {
+        // Key i appears i times
+        let input: String = (0..100)
+            .flat_map(|i| (0..i).map(move |j| format!("{i} {j}\n")))
+            .collect();
+        let field = |line: &[u8]| {
+            let field = line.split(|&c| c == b' ').next()?;
+            std::str::from_utf8(field).ok()?.parse::<u32>().ok()
+        };
+
+        let top = top_k(LineChunks::new(64, input.as_bytes()), 3, field).unwrap();
+        assert_eq!(top, vec![(99, 99), (98, 98), (97, 97)]);
+
+        let all = top_k(LineChunks::new(64, input.as_bytes()), 1000, field).unwrap();
+        assert_eq!(all.len(), 99);
+    }
+
+    #[test]
+    fn test_top_k_panic() {
+        // Unbounded input, so this only returns if the panic stops the reader.
+        let read = AtomicUsize::new(0);
+        let chunks = iter::repeat_with(|| {
+            read.fetch_add(1, Ordering::Relaxed);
+            Ok::<_, ()>(b"a\nb\n".to_vec())
+        });
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            top_k(chunks, 1, |line| -> Option<Vec<u8>> {
+                if line == b"b" {
+                    panic!("bad line")
+                }
+                Some(line.to_vec())
+            })
+        }));
+
+        let err = res.unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"bad line"));
+        assert!(read.into_inner() < 10000);
+    }
+
+    #[test]
+    fn test_group_by_key() {
+        let input: String = (0..1000).map(|i| format!("{} {i}\n", i % 3)).collect();
+        let groups = group_by_key(
+            LineChunks::new(64, input.as_bytes()),
+            |line| Some(line[0]),
+            |(count, sum): &mut (u64, u64), line| {
+                *count += 1;
+                *sum += std::str::from_utf8(&line[2..])
+                    .unwrap()
+                    .parse::<u64>()
+                    .unwrap();
+            },
+            |a, b| (a.0 + b.0, a.1 + b.1),
+        )
+        .unwrap();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&b'0'], (334, (0..1000).step_by(3).sum()));
+        assert_eq!(groups[&b'2'].0, 333);
+    }
+
+    #[test]
+    fn test_top_k_ties() {
+        let input = &b"b\na\nc\nb\na\nc\n"[..];
+        let top = top_k(LineChunks::new(4, input), 2, |line| Some(line.to_vec())).unwrap();
+
+        assert_eq!(top, vec![(b"a".to_vec(), 2), (b"b".to_vec(), 2)]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    future, io, mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncRead, BufReader};
+
+use crate::LineChunksError;
+
+/// Line-aligned chunks from an async reader, such as a socket.
+///
+/// This is the async counterpart of [`LineChunks`](crate::LineChunks), with
+/// the same chunk sizes and maximum line length, so reading doesn't block a
+/// thread. Chunks are returned by [`next_chunk`](Self::next_chunk), or
+/// from its [`Stream`] implementation, which composes with `StreamExt`
+/// combinators such as `map(..).buffered(n)` for concurrent processing.
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut chunks = linechunks::AsyncLineChunks::new(2, &b"a\nb\nc\n"[..]);
+/// while let Some(chunk) = chunks.next_chunk().await {
+///     let chunk = chunk?;
+///     // ...
+/// }
+/// # Ok::<_, std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct AsyncLineChunks<R> {
+    read: R,
+    chunksize: usize,
+    min_chunk: usize,
+    max_line: usize,
+    accum: Vec<u8>,
+    // Offset of the next byte to be consumed from `read`.
+    offset: u64,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncLineChunks<BufReader<R>> {
+    /// Wrap an unbuffered reader. `chunksize` sets the IO buffer size and the
+    /// default chunk sizes, as for [`LineChunks::new`](crate::LineChunks::new).
+    pub fn new(chunksize: usize, read: R) -> Self {
+        AsyncLineChunks::from_buf_read(chunksize, BufReader::with_capacity(chunksize, read))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncLineChunks<R> {
+    /// Read directly from the buffer of an [`AsyncBufRead`].
+    pub fn from_buf_read(chunksize: usize, read: R) -> Self {
+        AsyncLineChunks {
+            read,
+            chunksize,
+            min_chunk: chunksize * 3 / 4,
+            max_line: chunksize * 32,
+            accum: Vec::with_capacity(chunksize),
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Minimum chunk size. The last chunk may be shorter.
+    pub fn min_chunk(&mut self, size: usize) {
+        self.min_chunk = size;
+    }
+
+    /// Maximum line length. A longer line is an error, which ends the chunks.
+    pub fn max_line(&mut self, size: usize) {
+        self.max_line = size;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.read
+    }
+
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    /// The next chunk, or `None` at the end of the input. After an error,
+    /// there are no more chunks.
+    pub async fn next_chunk(&mut self) -> Option<io::Result<Vec<u8>>> {
+        future::poll_fn(|cx| self.poll_next_chunk(cx)).await
+    }
+
+    fn poll_next_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<Vec<u8>>>> {
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+
+            if self.accum.len() > self.max_line {
+                let keep = memchr::memrchr(b'\n', &self.accum).map_or(0, |eol| eol + 1);
+                if keep > 0 {
+                    // Return the complete lines first.
+                    let partial = self.accum.split_off(keep);
+                    return Poll::Ready(Some(Ok(mem::replace(&mut self.accum, partial))));
+                }
+
+                self.finished = true;
+                let partial = mem::take(&mut self.accum);
+                let err = LineChunksError::MaxLineExceeded {
+                    offset: self.offset - partial.len() as u64,
+                    accumulated: partial.len(),
+                    partial,
+                };
+                return Poll::Ready(Some(Err(err.into())));
+            }
+
+            let chunk = match ready!(Pin::new(&mut self.read).poll_fill_buf(cx)) {
+                Ok(chunk) => chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            };
+
+            if chunk.is_empty() {
+                self.finished = true;
+                let accum = mem::take(&mut self.accum);
+                return Poll::Ready((!accum.is_empty()).then_some(Ok(accum)));
+            }
+
+            let len = match memchr::memrchr(b'\n', chunk) {
+                Some(eol) => eol + 1,
+                None => chunk.len(),
+            };
+            self.accum.extend_from_slice(&chunk[..len]);
+            Pin::new(&mut self.read).consume(len);
+            self.offset += len as u64;
+
+            if self.accum.ends_with(b"\n") && self.accum.len() >= self.min_chunk {
+                let next = Vec::with_capacity(self.chunksize);
+                return Poll::Ready(Some(Ok(mem::replace(&mut self.accum, next))));
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncLineChunks<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_chunk(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::runtime::Builder;
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_async_line_chunks() {
+        let input: String = (0..1000)
+            .map(|i| format!("{}\n", "x".repeat(i % 37)))
+            .collect();
+        let expected: Vec<Vec<u8>> = LineChunks::new(64, input.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let mut chunks = AsyncLineChunks::new(64, input.as_bytes());
+                let mut got = Vec::new();
+                while let Some(chunk) = chunks.next_chunk().await {
+                    got.push(chunk.unwrap());
+                }
+                assert_eq!(got, expected);
+
+                let mut chunks = AsyncLineChunks::new(4, &b"ok\nmuch too long\n"[..]);
+                chunks.max_line(8);
+                chunks.min_chunk(1);
+                assert_eq!(chunks.next_chunk().await.unwrap().unwrap(), b"ok\n");
+                let err = chunks.next_chunk().await.unwrap().unwrap_err();
+                assert!(matches!(
+                    LineChunksError::from(err),
+                    LineChunksError::MaxLineExceeded { offset: 3, .. }
+                ));
+                assert!(chunks.next_chunk().await.is_none());
+            });
+    }
+
+    #[test]
+    fn test_stream() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+
+        Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let mut chunks = AsyncLineChunks::new(64, input.as_bytes());
+                let mut data = Vec::new();
+                while let Some(chunk) =
+                    future::poll_fn(|cx| Pin::new(&mut chunks).poll_next(cx)).await
+                {
+                    data.extend(chunk.unwrap());
+                }
+                assert_eq!(data, input.as_bytes());
+            });
+    }
+}
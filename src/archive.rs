@@ -0,0 +1,84 @@
+use std::io::{self, Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::LineChunks;
+
+/// Process the chunks of each file in a zip archive.
+///
+/// Each file entry is decompressed and chunked in turn, in archive order, and
+/// `f` is called with the entry's name and each of its line-aligned chunks.
+/// Chunks never span entries. Directory entries are skipped. Returns the first
+/// error from reading the archive or from `f`.
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let bundle = std::fs::File::open("logs.zip")?;
+/// linechunks::for_each_zip_chunk(bundle, 1 << 16, |name, chunk| {
+///     println!("{name}: {} bytes", chunk.len());
+///     Ok(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn for_each_zip_chunk<R, F>(reader: R, chunksize: usize, mut f: F) -> io::Result<()>
+where
+    R: Read + Seek,
+    F: FnMut(&str, Vec<u8>) -> io::Result<()>,
+{
+    let mut archive = ZipArchive::new(reader)?;
+
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry.name().to_string();
+        for chunk in LineChunks::new(chunksize, entry) {
+            f(&name, chunk?)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn test_zip() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        writer.start_file("a.log", stored).unwrap();
+        writer.write_all(b"a1\na2\na3\n").unwrap();
+        writer.add_directory("dir/", stored).unwrap();
+        writer.start_file("dir/b.log", deflated).unwrap();
+        for i in 0..1000 {
+            writeln!(writer, "b{i}").unwrap();
+        }
+        let archive = writer.finish().unwrap();
+
+        let mut lines = Vec::new();
+        for_each_zip_chunk(archive, 64, |name, chunk| {
+            assert!(chunk.ends_with(b"\n"));
+            for line in String::from_utf8(chunk).unwrap().lines() {
+                lines.push(format!("{name} {line}"));
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(lines.len(), 1003);
+        assert_eq!(lines[0], "a.log a1");
+        assert_eq!(lines[3], "dir/b.log b0");
+        assert_eq!(lines[1002], "dir/b.log b999");
+    }
+}
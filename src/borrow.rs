@@ -0,0 +1,95 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::LineChunks;
+
+/// Chunk a section of a borrowed reader, from [`LineChunks::by_ref`].
+///
+/// When this is dropped or [`finish`](Self::finish)ed, the reader is
+/// repositioned just after the last chunk returned, rather than wherever
+/// buffering left it, so it can carry on being used.
+pub struct ByRef<'a, R: Read + Seek> {
+    chunks: Option<LineChunks<&'a mut R>>,
+}
+
+impl<'a, R: Read + Seek> ByRef<'a, R> {
+    /// Stop chunking and reposition the reader, returning any error from
+    /// seeking it.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.rewind()
+    }
+
+    /// The `LineChunks`, for its configuration and state.
+    pub fn chunks(&mut self) -> &mut LineChunks<&'a mut R> {
+        self.chunks.as_mut().expect("chunks already finished")
+    }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        match self.chunks.take() {
+            Some(chunks) => {
+                let (read, pending) = chunks.into_parts();
+                read.seek(SeekFrom::Current(-(pending.len() as i64)))
+                    .map(drop)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for ByRef<'_, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.as_mut()?.next()
+    }
+}
+
+impl<R: Read + Seek> Drop for ByRef<'_, R> {
+    fn drop(&mut self) {
+        let _ = self.rewind();
+    }
+}
+
+impl<'a, R: Read + Seek> LineChunks<&'a mut R> {
+    /// Chunk a borrowed reader, leaving it positioned just after the last
+    /// chunk returned once done. This needs the reader to be seekable, to
+    /// give back input which was read ahead. For other readers, chunk a
+    /// `&mut` [`BufRead`](std::io::BufRead) with
+    /// [`from_buf_read`](LineChunks::from_buf_read) instead: unread input
+    /// stays in its buffer, though any partial line accumulated is only
+    /// available from [`into_parts`](LineChunks::into_parts).
+    pub fn by_ref(chunksize: usize, read: &'a mut R) -> ByRef<'a, R> {
+        ByRef {
+            chunks: Some(LineChunks::new(chunksize, read)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_by_ref() {
+        let mut read = Cursor::new(b"one\ntwo\nthree\nfour\n".to_vec());
+
+        let mut chunks = LineChunks::by_ref(1024, &mut read);
+        chunks.chunks().min_chunk(1);
+        chunks.chunks().max_line(1024);
+        // Everything is read in one go, but only the first chunk is used.
+        assert_eq!(chunks.next().unwrap().unwrap(), b"one\ntwo\nthree\nfour\n");
+        drop(chunks);
+        assert_eq!(read.position(), 19);
+
+        read.set_position(0);
+        let mut chunks = LineChunks::by_ref(4, &mut read);
+        chunks.chunks().min_chunk(1);
+        assert_eq!(chunks.next().unwrap().unwrap(), b"one\n");
+        chunks.finish().unwrap();
+
+        let mut rest = String::new();
+        read.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "two\nthree\nfour\n");
+    }
+}
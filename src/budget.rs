@@ -0,0 +1,222 @@
+use std::{
+    io,
+    ops::Deref,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+use crate::prefetch::{spawn_prefetch, Prefetch};
+
+/// A limit on the total bytes of chunks in flight, shared between stages.
+///
+/// [`max_line`](crate::LineChunks::max_line) bounds a single line, but with
+/// prefetching, pooling and consumers which hold on to chunks, the total can
+/// still grow. Chunks from [`budgeted`](Self::budgeted) are charged against
+/// the budget until they're dropped, and when it's used up, the prefetch
+/// thread from [`Budgeted::spawn_prefetch`] waits for chunks to be released
+/// before reading more. A budget can be cloned to share it between several
+/// chunkers.
+///
+/// A chunk is always handed out if nothing else is charged, even if it's
+/// larger than the whole budget, so a single oversized chunk can't stall the
+/// pipeline.
+#[derive(Clone, Debug)]
+pub struct MemoryBudget {
+    state: Arc<(Mutex<usize>, Condvar)>,
+    limit: usize,
+}
+
+impl MemoryBudget {
+    pub fn new(bytes: usize) -> Self {
+        MemoryBudget {
+            state: Arc::new((Mutex::new(0), Condvar::new())),
+            limit: bytes,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.state.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Bytes currently charged.
+    pub fn in_use(&self) -> usize {
+        *self.lock()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Charge chunks from `chunks`, such as a [`LineChunks`](crate::LineChunks)
+    /// or [`Pooled`](crate::Pooled), against this budget.
+    ///
+    /// ```
+    /// let budget = linechunks::MemoryBudget::new(1 << 20);
+    /// let chunks = linechunks::LineChunks::new(2, &b"a\nb\n"[..]);
+    /// for chunk in budget.budgeted(chunks) {
+    ///     let chunk = chunk?;
+    ///     assert_eq!(budget.in_use(), chunk.len());
+    /// }
+    /// assert_eq!(budget.in_use(), 0);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn budgeted<I>(&self, chunks: I) -> Budgeted<I> {
+        Budgeted {
+            chunks,
+            budget: self.clone(),
+            wait: false,
+        }
+    }
+
+    /// Charge `bytes`, waiting until they fit if `wait` is set.
+    fn acquire(&self, bytes: usize, wait: bool) {
+        let mut in_use = self.lock();
+        while wait && *in_use > 0 && *in_use + bytes > self.limit {
+            in_use = self
+                .state
+                .1
+                .wait(in_use)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        *in_use += bytes;
+    }
+
+    fn release(&self, bytes: usize) {
+        *self.lock() -= bytes;
+        self.state.1.notify_all();
+    }
+}
+
+/// Chunks charged against a [`MemoryBudget`], from
+/// [`MemoryBudget::budgeted`].
+///
+/// Iterating directly charges each chunk without waiting, since the thread
+/// which would have to release chunks is the one iterating, so the budget
+/// can be exceeded. Waiting for the budget happens on the thread from
+/// [`spawn_prefetch`](Self::spawn_prefetch).
+pub struct Budgeted<I> {
+    chunks: I,
+    budget: MemoryBudget,
+    // Running on the prefetch thread, so wait for the budget.
+    wait: bool,
+}
+
+impl<I> Budgeted<I> {
+    pub fn into_inner(self) -> I {
+        self.chunks
+    }
+}
+
+impl<I, T> Iterator for Budgeted<I>
+where
+    I: Iterator<Item = io::Result<T>>,
+    T: AsRef<[u8]>,
+{
+    type Item = io::Result<Charged<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        Some(chunk.map(|chunk| {
+            let bytes = chunk.as_ref().len();
+            self.budget.acquire(bytes, self.wait);
+            Charged {
+                chunk: Some(chunk),
+                bytes,
+                budget: self.budget.clone(),
+            }
+        }))
+    }
+}
+
+impl<I, T> Budgeted<I>
+where
+    I: Iterator<Item = io::Result<T>> + Send + 'static,
+    T: AsRef<[u8]> + Send + 'static,
+{
+    /// Read ahead on a new thread, like
+    /// [`LineChunks::spawn_prefetch`](crate::LineChunks::spawn_prefetch),
+    /// but also stopping when the budget is used up.
+    pub fn spawn_prefetch(mut self, n: usize) -> Prefetch<Charged<T>> {
+        self.wait = true;
+        spawn_prefetch(self, n)
+    }
+}
+
+/// A chunk charged against a [`MemoryBudget`] until it's dropped.
+pub struct Charged<T> {
+    chunk: Option<T>,
+    bytes: usize,
+    budget: MemoryBudget,
+}
+
+impl<T> Charged<T> {
+    /// Release the chunk from the budget, to keep it.
+    pub fn into_inner(mut self) -> T {
+        self.chunk.take().unwrap()
+    }
+}
+
+impl<T> Deref for Charged<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.chunk.as_ref().unwrap()
+    }
+}
+
+impl<T> Drop for Charged<T> {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::{BufferPool, LineChunks};
+
+    #[test]
+    fn test_memory_budget() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let budget = MemoryBudget::new(200);
+
+        // The prefetch thread stops once the held chunks use up the budget.
+        let chunks = LineChunks::new(64, io::Cursor::new(input.clone())).pooled(BufferPool::new(4));
+        let mut chunks = budget.budgeted(chunks).spawn_prefetch(16);
+        let held: Vec<_> = (0..3).map(|_| chunks.next().unwrap().unwrap()).collect();
+        thread::sleep(Duration::from_millis(20));
+        assert!(budget.in_use() <= 200, "{}", budget.in_use());
+
+        let mut out: Vec<u8> = held
+            .iter()
+            .flat_map(|chunk| chunk.iter().copied())
+            .collect();
+        drop(held);
+        for chunk in chunks {
+            out.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(out, input.as_bytes());
+        assert_eq!(budget.in_use(), 0);
+
+        // A chunk larger than the budget still goes through on its own.
+        let tiny = MemoryBudget::new(1);
+        let kept = tiny.budgeted(LineChunks::new(64, input.as_bytes())).next();
+        assert_eq!(tiny.in_use(), kept.unwrap().unwrap().len());
+    }
+
+    #[test]
+    fn test_single_thread() {
+        // Keeping every chunk on one thread goes over the budget rather
+        // than waiting for chunks which will never be released.
+        let budget = MemoryBudget::new(8);
+        let chunks: Vec<_> = budget
+            .budgeted(LineChunks::new(4, &b"aaa\nbbb\nccc\n"[..]))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(budget.in_use(), 12);
+        drop(chunks);
+        assert_eq!(budget.in_use(), 0);
+    }
+}
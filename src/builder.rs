@@ -0,0 +1,167 @@
+use std::io::{self, ErrorKind, Read};
+
+use crate::{ChunkConfig, ErrorPolicy, LineChunks, OversizePolicy};
+
+/// Builder for [`LineChunks`], from [`LineChunks::builder`].
+///
+/// Settings which aren't given default as they do for
+/// [`LineChunks::new`], and the chunk size defaults to 64KiB. Settings are
+/// checked for consistency when the builder is built, either into a
+/// `LineChunks` or a reusable [`ChunkConfig`].
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// let input = &b"one\ntwo\nthree\n"[..];
+/// let chunks = linechunks::LineChunks::builder()
+///     .chunk_size(1 << 20)
+///     .min_chunk(512 * 1024)
+///     .max_line(4 << 20)
+///     .build(input)?;
+/// assert_eq!(chunks.count(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LineChunksBuilder {
+    config: ChunkConfig,
+    min_chunk_ratio: Option<f64>,
+}
+
+impl LineChunksBuilder {
+    pub fn new() -> Self {
+        LineChunksBuilder::default()
+    }
+
+    /// Size of the IO buffer. See [`LineChunks::new`].
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.config.chunk_size = size;
+        self
+    }
+
+    /// See [`LineChunks::min_chunk`]. This must be no more than the chunk
+    /// size.
+    pub fn min_chunk(mut self, size: usize) -> Self {
+        self.config.min_chunk = Some(size);
+        self
+    }
+
+    /// Set the minimum chunk size as a fraction of the chunk size. See
+    /// [`LineChunks::min_chunk_ratio`]. This must be between 0 and 1, and
+    /// overrides `min_chunk`.
+    pub fn min_chunk_ratio(mut self, ratio: f64) -> Self {
+        self.min_chunk_ratio = Some(ratio);
+        self
+    }
+
+    /// See [`LineChunks::max_line`]. This must be at least the chunk size.
+    pub fn max_line(mut self, size: usize) -> Self {
+        self.config.max_line = Some(size);
+        self
+    }
+
+    /// See [`LineChunks::error_policy`].
+    pub fn error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.config.error_policy = policy;
+        self
+    }
+
+    /// See [`LineChunks::oversize_policy`].
+    pub fn oversize_policy(mut self, policy: OversizePolicy) -> Self {
+        self.config.oversize_policy = policy;
+        self
+    }
+
+    /// See [`LineChunks::recoverable`].
+    pub fn recoverable(mut self, recoverable: bool) -> Self {
+        self.config.recoverable = recoverable;
+        self
+    }
+
+    /// Check the settings and return them as a [`ChunkConfig`], for making
+    /// many chunkers. Returns an [`ErrorKind::InvalidInput`] error if the
+    /// settings are inconsistent.
+    pub fn config(&self) -> io::Result<ChunkConfig> {
+        let invalid = |msg: String| Err(io::Error::new(ErrorKind::InvalidInput, msg));
+        let mut config = self.config.clone();
+
+        if config.chunk_size == 0 {
+            return invalid("chunk size must be non-zero".to_string());
+        }
+        if let Some(ratio) = self.min_chunk_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return invalid(format!("min_chunk_ratio {ratio} is not between 0 and 1"));
+            }
+            config.min_chunk = Some((config.chunk_size as f64 * ratio) as usize);
+        }
+        if let Some(min_chunk) = config.min_chunk.filter(|&min| min > config.chunk_size) {
+            return invalid(format!(
+                "min_chunk {min_chunk} is larger than chunk size {}",
+                config.chunk_size
+            ));
+        }
+        if let Some(max_line) = config.max_line.filter(|&max| max < config.chunk_size) {
+            return invalid(format!(
+                "max_line {max_line} is smaller than chunk size {}",
+                config.chunk_size
+            ));
+        }
+
+        Ok(config)
+    }
+
+    /// Build a [`LineChunks`] reading from `read`, or return an
+    /// [`ErrorKind::InvalidInput`] error if the settings are inconsistent.
+    pub fn build<R: Read>(&self, read: R) -> io::Result<LineChunks<R>> {
+        Ok(self.config()?.chunker(read))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let input = &b"one\ntwo\nthree\nfour\n"[..];
+        let chunks: Vec<_> = LineChunksBuilder::new()
+            .chunk_size(4)
+            .min_chunk(1)
+            .build(input)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks, [&b"one\n"[..], b"two\n", b"three\n", b"four\n"]);
+
+        let res = LineChunksBuilder::new()
+            .chunk_size(1024)
+            .min_chunk(2048)
+            .build(input);
+        assert_eq!(res.err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert!(LineChunksBuilder::new().chunk_size(0).build(input).is_err());
+        assert!(LineChunksBuilder::new().max_line(10).build(input).is_err());
+        assert!(LineChunksBuilder::new()
+            .min_chunk_ratio(1.5)
+            .build(input)
+            .is_err());
+    }
+
+    #[test]
+    fn test_policies() {
+        let builder = LineChunksBuilder::new()
+            .chunk_size(4)
+            .min_chunk(1)
+            .max_line(8)
+            .error_policy(ErrorPolicy::SkipAndCount)
+            .oversize_policy(OversizePolicy::SkipLine);
+        let config = builder.config().unwrap();
+        assert_eq!(config.error_policy, ErrorPolicy::SkipAndCount);
+        assert_eq!(config.oversize_policy, OversizePolicy::SkipLine);
+
+        let chunks: Vec<_> = builder
+            .build(&b"a\nmuch too long\nb\n"[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks, [&b"a\n"[..], b"b\n"]);
+    }
+}
@@ -0,0 +1,222 @@
+use std::mem;
+
+use crate::sample::mix;
+
+/// Random values for the gear hash, one per byte value.
+const GEAR: [u64; 256] = {
+    let mut gear = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        gear[i] = mix(0x6765_6172, i as u64);
+        i += 1;
+    }
+    gear
+};
+
+/// A mask of the top `bits` bits. Each bit of the gear hash depends on the
+/// preceding bytes up to its position, so the top bits cover the most input.
+const fn top_bits(bits: u32) -> u64 {
+    !0 << (64 - bits)
+}
+
+/// Regroup line-aligned chunks at content-defined boundaries.
+///
+/// Cut points are found with a FastCDC-style gear hash, then moved forward to
+/// the end of the line they fall in, so chunks are still line-aligned. The
+/// cut points only depend on nearby content, so inserting or deleting lines
+/// only changes the chunks around the edit and the rest keep their identity.
+/// This makes chunk hashes useful for caching and dedup across versions of a
+/// file.
+///
+/// Chunks are at least `avg_size / 4` bytes, and are cut at the next line
+/// boundary once they reach `avg_size * 8`. Errors are passed through.
+pub struct CdcChunks<I> {
+    chunks: I,
+    finished: bool,
+    min: usize,
+    avg: usize,
+    max: usize,
+    // Stricter mask below the average size and looser above, to narrow the
+    // size distribution.
+    mask_small: u64,
+    mask_large: u64,
+    buf: Vec<u8>,
+    // Start of the current chunk in `buf`, and how far it has been hashed.
+    start: usize,
+    scanned: usize,
+    hash: u64,
+    // A cut point was found here, and the chunk ends at the next `\n`.
+    cut: Option<usize>,
+}
+
+impl<I, E> CdcChunks<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    /// Panics if `avg_size` is less than 16.
+    pub fn new(chunks: I, avg_size: usize) -> Self {
+        assert!(avg_size >= 16, "average chunk size must be at least 16");
+        let bits = avg_size.ilog2();
+
+        CdcChunks {
+            chunks,
+            finished: false,
+            min: avg_size / 4,
+            avg: avg_size,
+            max: avg_size * 8,
+            mask_small: top_bits(bits + 2),
+            mask_large: top_bits(bits - 2),
+            buf: Vec::new(),
+            start: 0,
+            scanned: 0,
+            hash: 0,
+            cut: None,
+        }
+    }
+
+    /// Find the end of the current chunk, if it's in `buf` yet.
+    fn find_cut(&mut self) -> Option<usize> {
+        let mut i = self.scanned;
+
+        while self.cut.is_none() && i < self.buf.len() {
+            let len = i - self.start;
+            if len < self.min {
+                i = (self.start + self.min).min(self.buf.len());
+                continue;
+            }
+            if len >= self.max {
+                self.cut = Some(i);
+                break;
+            }
+
+            self.hash = (self.hash << 1).wrapping_add(GEAR[self.buf[i] as usize]);
+            let mask = if len < self.avg {
+                self.mask_small
+            } else {
+                self.mask_large
+            };
+            if self.hash & mask == 0 {
+                self.cut = Some(i);
+            }
+            i += 1;
+        }
+        self.scanned = i;
+
+        let cut = self.cut?;
+        match memchr::memchr(b'\n', &self.buf[cut..]) {
+            Some(eol) => {
+                let end = cut + eol + 1;
+                self.cut = None;
+                self.hash = 0;
+                self.scanned = end;
+                Some(end)
+            }
+            None => {
+                self.cut = Some(self.buf.len());
+                self.scanned = self.buf.len();
+                None
+            }
+        }
+    }
+
+    /// Drop the bytes already returned from the front of `buf`.
+    fn compact(&mut self) {
+        self.buf.drain(..self.start);
+        self.scanned -= self.start;
+        if let Some(cut) = &mut self.cut {
+            *cut -= self.start;
+        }
+        self.start = 0;
+    }
+}
+
+impl<I, E> Iterator for CdcChunks<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    type Item = Result<Vec<u8>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(end) = self.find_cut() {
+                let chunk = self.buf[self.start..end].to_vec();
+                self.start = end;
+                break Some(Ok(chunk));
+            }
+
+            if self.finished {
+                self.compact();
+                let rest = mem::take(&mut self.buf);
+                self.scanned = 0;
+                self.cut = None;
+                break if rest.is_empty() {
+                    None
+                } else {
+                    Some(Ok(rest))
+                };
+            }
+
+            match self.chunks.next() {
+                Some(Ok(chunk)) => {
+                    self.compact();
+                    self.buf.extend_from_slice(&chunk);
+                }
+                Some(Err(err)) => break Some(Err(err)),
+                None => self.finished = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::LineChunks;
+
+    fn cdc(input: &[u8]) -> Vec<Vec<u8>> {
+        CdcChunks::new(LineChunks::new(1024, input), 512)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_cdc() {
+        let input: String = (0..20_000)
+            .map(|i| format!("line {}\n", i * 7919 % 10007))
+            .collect();
+        let chunks = cdc(input.as_bytes());
+
+        assert_eq!(chunks.concat(), input.as_bytes());
+        assert!(chunks.len() > 100);
+        for chunk in &chunks {
+            assert!(chunk.ends_with(b"\n"));
+            assert!(chunk.len() <= 512 * 8 + 20);
+        }
+        assert!(chunks[..chunks.len() - 1]
+            .iter()
+            .all(|chunk| chunk.len() >= 128));
+    }
+
+    #[test]
+    fn test_cdc_insert() {
+        let lines: Vec<String> = (0..20_000)
+            .map(|i| format!("line {}\n", i * 7919 % 10007))
+            .collect();
+        let before = cdc(lines.concat().as_bytes());
+
+        let mut edited = lines.clone();
+        edited.insert(5000, "inserted\n".to_string());
+        edited.remove(15000);
+        let after = cdc(edited.concat().as_bytes());
+
+        // Only the chunks around each edit change
+        let before: HashSet<_> = before.into_iter().collect();
+        let changed = after
+            .iter()
+            .filter(|chunk| !before.contains(*chunk))
+            .count();
+        assert!(changed <= 4, "{changed} of {} chunks changed", after.len());
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::{self, Read};
+
+use crate::LineChunks;
+
+/// Chunk a sequence of readers as one stream, without any chunk spanning two
+/// readers.
+///
+/// Unlike chunking readers joined with [`Read::chain`], each reader's last
+/// chunk is returned when it reaches EOF, even if it's short or has no final
+/// `\n`, so every chunk comes from exactly one source. Use
+/// [`source`](Self::source) to find out which.
+pub struct ChainChunks<I: Iterator> {
+    readers: I,
+    chunksize: usize,
+    max_line: Option<usize>,
+    min_chunk: Option<usize>,
+    current: Option<LineChunks<I::Item>>,
+    // Index of the current reader, counting from 0.
+    source: usize,
+}
+
+impl<I> ChainChunks<I>
+where
+    I: Iterator,
+    I::Item: Read,
+{
+    pub fn new<T>(chunksize: usize, readers: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        ChainChunks {
+            readers: readers.into_iter(),
+            chunksize,
+            max_line: None,
+            min_chunk: None,
+            current: None,
+            source: 0,
+        }
+    }
+
+    /// Max line length for each reader. See [`LineChunks::max_line`].
+    pub fn max_line(&mut self, size: usize) {
+        self.max_line = Some(size);
+    }
+
+    /// Minimum chunk size for each reader. See [`LineChunks::min_chunk`].
+    pub fn min_chunk(&mut self, size: usize) {
+        self.min_chunk = Some(size);
+    }
+
+    /// Index of the reader the last chunk came from, counting from 0.
+    pub fn source(&self) -> usize {
+        self.source.saturating_sub(1)
+    }
+}
+
+impl<I> Iterator for ChainChunks<I>
+where
+    I: Iterator,
+    I::Item: Read,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(chunk) = self.current.as_mut().and_then(Iterator::next) {
+                break Some(chunk);
+            }
+
+            let mut chunks = LineChunks::new(self.chunksize, self.readers.next()?);
+            if let Some(size) = self.max_line {
+                chunks.max_line(size);
+            }
+            if let Some(size) = self.min_chunk {
+                chunks.min_chunk(size);
+            }
+            self.current = Some(chunks);
+            self.source += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chain() {
+        let inputs = [&b"a1\na2"[..], b"", b"b1\nb2\nb3\n", b"c1"];
+        let mut chunks = ChainChunks::new(1024, inputs);
+
+        let mut got = Vec::new();
+        while let Some(chunk) = chunks.next() {
+            got.push((chunks.source(), chunk.unwrap()));
+        }
+
+        assert_eq!(
+            got,
+            vec![
+                (0, b"a1\na2".to_vec()),
+                (2, b"b1\nb2\nb3\n".to_vec()),
+                (3, b"c1".to_vec()),
+            ]
+        );
+    }
+}
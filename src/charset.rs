@@ -0,0 +1,101 @@
+use std::io::{self, Chain, Cursor, Read};
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+
+// How much of the input to examine when guessing its encoding.
+const SNIFF_LEN: usize = 64 * 1024;
+
+/// Charset detection and transcoding to UTF-8.
+///
+/// Wraps a [`Read`]er whose encoding isn't known, examines the start of the
+/// input to guess it, and then transcodes everything read to UTF-8. A byte
+/// order mark identifies UTF-8 or UTF-16 (and is removed). Otherwise the
+/// encoding is guessed from the content, which distinguishes UTF-8 from the
+/// legacy single and multi-byte encodings, but not UTF-16 without a BOM.
+/// UTF-8 input is passed through as it is.
+///
+/// ```
+/// use linechunks::{DetectCharset, LineChunks};
+///
+/// let input = &b"caf\xe9 cr\xe8me br\xfbl\xe9e\n"[..];
+/// let reader = DetectCharset::new(input)?;
+/// assert_eq!(reader.encoding().name(), "windows-1252");
+///
+/// let chunks: Vec<_> = LineChunks::new(8192, reader).collect::<Result<_, _>>()?;
+/// assert_eq!(chunks, vec!["café crème brûlée\n".as_bytes().to_vec()]);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct DetectCharset<R> {
+    inner: DecodeReaderBytes<Chain<Cursor<Vec<u8>>, R>, Vec<u8>>,
+    encoding: &'static Encoding,
+}
+
+impl<R: Read> DetectCharset<R> {
+    /// Read the start of the input and guess its encoding.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        (&mut inner).take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+
+        let encoding = match Encoding::for_bom(&head) {
+            Some((encoding, _)) => encoding,
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(&head, head.len() < SNIFF_LEN);
+                detector.guess(None, true)
+            }
+        };
+
+        let inner = DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .utf8_passthru(true)
+            .build(Cursor::new(head).chain(inner));
+
+        Ok(DetectCharset { inner, encoding })
+    }
+
+    /// The detected encoding.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// Whether the input is being transcoded, rather than passed through as
+    /// UTF-8.
+    pub fn is_transcoding(&self) -> bool {
+        self.encoding != UTF_8
+    }
+}
+
+impl<R: Read> Read for DetectCharset<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(input: &[u8]) -> (&'static str, bool, String) {
+        let mut reader = DetectCharset::new(input).unwrap();
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        (reader.encoding().name(), reader.is_transcoding(), out)
+    }
+
+    #[test]
+    fn test_detect() {
+        let text = "naïve café\nÜber große Straße\n";
+
+        let utf8 = decode(text.as_bytes());
+        assert_eq!(utf8, ("UTF-8", false, text.to_string()));
+
+        let mut utf16: Vec<u8> = vec![0xff, 0xfe];
+        utf16.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode(&utf16), ("UTF-16LE", true, text.to_string()));
+
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode(text);
+        assert_eq!(decode(&latin1), ("windows-1252", true, text.to_string()));
+    }
+}
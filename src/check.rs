@@ -0,0 +1,208 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc::Sender,
+    Mutex,
+};
+
+use crate::splitter;
+
+/// What [`LineCheck`] does with a line which fails its check.
+pub enum CheckPolicy {
+    /// Fail the whole chunk with the check's error.
+    Reject,
+    /// Drop the line from the chunk.
+    Skip,
+    /// Drop the line from the chunk, and send a copy of it (without its `\n`)
+    /// to a side channel.
+    Quarantine(Sender<Vec<u8>>),
+}
+
+/// Summary of the lines seen by a [`LineCheck`].
+#[derive(Debug)]
+pub struct CheckSummary<E> {
+    /// Total number of lines checked.
+    pub lines: u64,
+    /// Number of lines which failed the check.
+    pub violations: u64,
+    /// The errors for the first failed lines, up to the `keep_errors` limit.
+    pub errors: Vec<E>,
+}
+
+/// Per-line validation stage.
+///
+/// Runs a user check function over every non-empty line of each chunk, and
+/// applies a [`CheckPolicy`] to the lines which fail. The check is shared by
+/// reference so it can be applied to chunks in parallel, for example from a
+/// rayon `map` over `LineChunks::par_bridge()`. Once all chunks have been
+/// processed, [`LineCheck::into_summary`] returns the totals.
+pub struct LineCheck<F, E> {
+    check: F,
+    policy: CheckPolicy,
+    keep_errors: usize,
+    lines: AtomicU64,
+    violations: AtomicU64,
+    errors: Mutex<Vec<E>>,
+}
+
+impl<F, E> LineCheck<F, E>
+where
+    F: Fn(&[u8]) -> Result<(), E>,
+{
+    pub fn new(check: F, policy: CheckPolicy) -> Self {
+        LineCheck {
+            check,
+            policy,
+            keep_errors: 100,
+            lines: AtomicU64::new(0),
+            violations: AtomicU64::new(0),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Number of errors to keep for the summary. Violations beyond this are
+    /// still counted. Defaults to 100.
+    pub fn keep_errors(&mut self, count: usize) {
+        self.keep_errors = count;
+    }
+
+    /// Check every line in `chunk`, returning the chunk with any rejected
+    /// lines removed. With [`CheckPolicy::Reject`] the first failure is
+    /// returned as the error instead.
+    pub fn check_chunk(&self, chunk: Vec<u8>) -> Result<Vec<u8>, E> {
+        let mut lines = 0;
+        let mut keep = None::<Vec<u8>>;
+
+        for span in splitter::spans(&chunk) {
+            let line = &chunk[span.start..span.eol];
+
+            let res = if line.is_empty() {
+                Ok(())
+            } else {
+                lines += 1;
+                (self.check)(line)
+            };
+
+            match res {
+                Ok(()) => {
+                    if let Some(keep) = &mut keep {
+                        keep.extend_from_slice(&chunk[span.start..span.next]);
+                    }
+                }
+                Err(err) => {
+                    self.lines.fetch_add(lines, Ordering::Relaxed);
+                    lines = 0;
+
+                    if let CheckPolicy::Reject = self.policy {
+                        self.violations.fetch_add(1, Ordering::Relaxed);
+                        return Err(err);
+                    }
+                    self.violation(err);
+
+                    if let CheckPolicy::Quarantine(side) = &self.policy {
+                        // If the receiver has gone away there's nowhere left to
+                        // put the line, so just drop it.
+                        let _ = side.send(line.to_vec());
+                    }
+
+                    // Start building a filtered copy of the chunk from the
+                    // first rejected line on.
+                    keep.get_or_insert_with(|| {
+                        let mut keep = Vec::with_capacity(chunk.len());
+                        keep.extend_from_slice(&chunk[..span.start]);
+                        keep
+                    });
+                }
+            }
+        }
+
+        self.lines.fetch_add(lines, Ordering::Relaxed);
+        Ok(keep.unwrap_or(chunk))
+    }
+
+    fn violation(&self, err: E) {
+        self.violations.fetch_add(1, Ordering::Relaxed);
+
+        let mut errors = self.errors.lock().unwrap_or_else(|err| err.into_inner());
+        if errors.len() < self.keep_errors {
+            errors.push(err);
+        }
+    }
+
+    /// Return the summary of all the lines checked.
+    pub fn into_summary(self) -> CheckSummary<E> {
+        CheckSummary {
+            lines: self.lines.into_inner(),
+            violations: self.violations.into_inner(),
+            errors: self
+                .errors
+                .into_inner()
+                .unwrap_or_else(|err| err.into_inner()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::LineChunks;
+
+    fn digits(line: &[u8]) -> Result<(), String> {
+        if line.iter().all(u8::is_ascii_digit) {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(line).into_owned())
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        let check = LineCheck::new(digits, CheckPolicy::Skip);
+        let chunk = check.check_chunk(b"1\nx\n\n2\ny\n3".to_vec()).unwrap();
+
+        assert_eq!(chunk, b"1\n\n2\n3");
+        let summary = check.into_summary();
+        assert_eq!(summary.lines, 5);
+        assert_eq!(summary.violations, 2);
+        assert_eq!(summary.errors, vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_reject() {
+        let check = LineCheck::new(digits, CheckPolicy::Reject);
+
+        assert_eq!(check.check_chunk(b"1\n2\n".to_vec()).unwrap(), b"1\n2\n");
+        assert_eq!(check.check_chunk(b"1\nx\n".to_vec()).unwrap_err(), "x");
+        assert_eq!(check.into_summary().violations, 1);
+    }
+
+    #[test]
+    fn test_quarantine_par() {
+        let input: String = (0..10000)
+            .map(|i| {
+                if i % 100 == 0 {
+                    format!("bad{i}\n")
+                } else {
+                    format!("{i}\n")
+                }
+            })
+            .collect();
+        let (send, recv) = mpsc::channel();
+        let check = LineCheck::new(digits, CheckPolicy::Quarantine(send));
+
+        let good: usize = LineChunks::new(256, input.as_bytes())
+            .par_bridge()
+            .map(|chunk| check.check_chunk(chunk.unwrap()).unwrap())
+            .map(|chunk| chunk.iter().filter(|&&c| c == b'\n').count())
+            .sum();
+
+        assert_eq!(good, 9900);
+        let summary = check.into_summary();
+        assert_eq!(summary.lines, 10000);
+        assert_eq!(summary.violations, 100);
+        assert_eq!(summary.errors.len(), 100);
+        assert_eq!(recv.iter().count(), 100);
+    }
+}
@@ -0,0 +1,130 @@
+use std::cmp::Ordering;
+
+use crate::lines::ChunkLines;
+
+/// A line yielded by [`Comm`], tagged with which inputs it was in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommLine {
+    /// Only in the first input.
+    OnlyA(Vec<u8>),
+    /// Only in the second input.
+    OnlyB(Vec<u8>),
+    /// In both inputs.
+    Both(Vec<u8>),
+}
+
+/// Compare two sorted streams of line-aligned chunks, like `comm(1)`.
+///
+/// Both inputs must be sorted bytewise. Lines are yielded in sorted order,
+/// without their `\n`, tagged with whether they were in one input or both.
+/// Duplicate lines are paired off one for one, so a line appearing twice in A
+/// and once in B is yielded once as [`CommLine::Both`] and once as
+/// [`CommLine::OnlyA`]. Empty lines are skipped.
+///
+/// Only one line from each input is held at a time, so inputs of any size can
+/// be compared.
+pub struct Comm<A, B> {
+    a: ChunkLines<A>,
+    b: ChunkLines<B>,
+    finished: bool,
+    next_a: Option<Vec<u8>>,
+    next_b: Option<Vec<u8>>,
+}
+
+impl<A, B, E> Comm<A, B>
+where
+    A: Iterator<Item = Result<Vec<u8>, E>>,
+    B: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    pub fn new(a: A, b: B) -> Self {
+        Comm {
+            a: ChunkLines::new(a),
+            b: ChunkLines::new(b),
+            finished: false,
+            next_a: None,
+            next_b: None,
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<CommLine>, E> {
+        if self.next_a.is_none() {
+            self.next_a = self.a.next().transpose()?;
+        }
+        if self.next_b.is_none() {
+            self.next_b = self.b.next().transpose()?;
+        }
+
+        let ord = match (&self.next_a, &self.next_b) {
+            (None, None) => return Ok(None),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(a), Some(b)) => a.cmp(b),
+        };
+
+        let line = match ord {
+            Ordering::Less => CommLine::OnlyA(self.next_a.take().expect("missing line")),
+            Ordering::Greater => CommLine::OnlyB(self.next_b.take().expect("missing line")),
+            Ordering::Equal => {
+                self.next_b = None;
+                CommLine::Both(self.next_a.take().expect("missing line"))
+            }
+        };
+
+        Ok(Some(line))
+    }
+}
+
+impl<A, B, E> Iterator for Comm<A, B>
+where
+    A: Iterator<Item = Result<Vec<u8>, E>>,
+    B: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    type Item = Result<CommLine, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.step() {
+            Ok(Some(line)) => Some(Ok(line)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_comm() {
+        let a = &b"apple\nbanana\nbanana\ncherry\nfig\n"[..];
+        let b = &b"banana\ncherry\ndate\nfig\ngrape"[..];
+
+        let lines: Vec<_> = Comm::new(LineChunks::new(8, a), LineChunks::new(8, b))
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                CommLine::OnlyA(b"apple".to_vec()),
+                CommLine::Both(b"banana".to_vec()),
+                CommLine::OnlyA(b"banana".to_vec()),
+                CommLine::Both(b"cherry".to_vec()),
+                CommLine::OnlyB(b"date".to_vec()),
+                CommLine::Both(b"fig".to_vec()),
+                CommLine::OnlyB(b"grape".to_vec()),
+            ]
+        );
+    }
+}
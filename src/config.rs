@@ -0,0 +1,104 @@
+use std::io::Read;
+
+use crate::{ErrorPolicy, LineChunks, OversizePolicy};
+
+/// Settings for [`LineChunks`], for making many chunkers configured the same
+/// way, such as one per file in a directory walk.
+///
+/// Settings which are `None` default as they do for [`LineChunks::new`]. Use
+/// [`LineChunksBuilder::config`](crate::LineChunksBuilder::config) to build
+/// a config with the settings checked for consistency.
+///
+/// ```
+/// use linechunks::ChunkConfig;
+///
+/// let config = ChunkConfig {
+///     chunk_size: 1 << 20,
+///     max_line: Some(16 << 20),
+///     ..ChunkConfig::default()
+/// };
+/// for input in [&b"a\nb\n"[..], b"c\n"] {
+///     for chunk in config.chunker(input) {
+///         let chunk = chunk?;
+///         // ...
+///     }
+/// }
+/// # Ok::<_, std::io::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkConfig {
+    /// Size of the IO buffer. See [`LineChunks::new`].
+    pub chunk_size: usize,
+    /// See [`LineChunks::min_chunk`].
+    pub min_chunk: Option<usize>,
+    /// See [`LineChunks::max_line`].
+    pub max_line: Option<usize>,
+    /// See [`LineChunks::error_policy`].
+    pub error_policy: ErrorPolicy,
+    /// See [`LineChunks::oversize_policy`].
+    pub oversize_policy: OversizePolicy,
+    /// See [`LineChunks::recoverable`].
+    pub recoverable: bool,
+}
+
+impl Default for ChunkConfig {
+    /// A 64KiB chunk size, with everything else defaulted.
+    fn default() -> Self {
+        ChunkConfig {
+            chunk_size: 64 * 1024,
+            min_chunk: None,
+            max_line: None,
+            error_policy: ErrorPolicy::default(),
+            oversize_policy: OversizePolicy::default(),
+            recoverable: false,
+        }
+    }
+}
+
+impl ChunkConfig {
+    /// Make a [`LineChunks`] with these settings, reading from `read`.
+    pub fn chunker<R: Read>(&self, read: R) -> LineChunks<R> {
+        let mut chunks = LineChunks::new(self.chunk_size, read);
+        if let Some(min_chunk) = self.min_chunk {
+            chunks.min_chunk(min_chunk);
+        }
+        if let Some(max_line) = self.max_line {
+            chunks.max_line(max_line);
+        }
+        chunks.error_policy(self.error_policy);
+        chunks.oversize_policy(self.oversize_policy);
+        chunks.recoverable(self.recoverable);
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = ChunkConfig {
+            chunk_size: 4,
+            min_chunk: Some(1),
+            max_line: Some(8),
+            oversize_policy: OversizePolicy::SkipLine,
+            ..ChunkConfig::default()
+        };
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let config = config.clone();
+                thread::spawn(move || {
+                    let input = &b"a\nmuch too long\nb\n"[..];
+                    config.chunker(input).map(Result::unwrap).count()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2);
+        }
+    }
+}
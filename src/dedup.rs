@@ -0,0 +1,155 @@
+use std::{
+    f64::consts::LN_2,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::sample::mix;
+
+fn hash(line: &[u8]) -> u64 {
+    let mut words = line.chunks_exact(8);
+    let mut h = mix(0, line.len() as u64);
+    for word in words.by_ref() {
+        h = mix(h, u64::from_le_bytes(word.try_into().unwrap()));
+    }
+
+    let mut tail = [0; 8];
+    tail[..words.remainder().len()].copy_from_slice(words.remainder());
+    mix(h, u64::from_le_bytes(tail))
+}
+
+/// Approximate line dedup with a Bloom filter.
+///
+/// For inputs too large to keep an exact set of seen lines, this drops lines
+/// which are probably duplicates, using a fixed amount of memory sized for an
+/// expected number of distinct lines and a false-positive rate. A line is
+/// never dropped the first time it's seen except as a false positive, and
+/// every repeat of a line is dropped.
+///
+/// The filter is shared with `&self`, so chunks from any number of files can
+/// be deduped in parallel. If two copies of a new line are inserted at the
+/// same time, both may be kept.
+pub struct BloomDedup {
+    bits: Vec<AtomicU64>,
+    nbits: u64,
+    hashes: u32,
+    lines: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl BloomDedup {
+    /// Size the filter for `expected` distinct lines with a false-positive
+    /// rate of `fp_rate` once they've all been seen. Panics if `fp_rate` isn't
+    /// strictly between 0 and 1.
+    pub fn new(expected: u64, fp_rate: f64) -> Self {
+        assert!(
+            fp_rate > 0.0 && fp_rate < 1.0,
+            "false positive rate must be between 0 and 1"
+        );
+
+        let expected = expected.max(1) as f64;
+        let nbits = (-expected * fp_rate.ln() / (LN_2 * LN_2)).ceil().max(64.0) as u64;
+        let hashes = (nbits as f64 / expected * LN_2).round().clamp(1.0, 32.0) as u32;
+
+        BloomDedup {
+            bits: (0..nbits.div_ceil(64)).map(|_| AtomicU64::new(0)).collect(),
+            nbits,
+            hashes,
+            lines: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Add `line` to the filter, returning true if it wasn't there before.
+    pub fn insert(&self, line: &[u8]) -> bool {
+        let h1 = hash(line);
+        let h2 = mix(h1, 0) | 1;
+        let mut new = false;
+
+        for i in 0..self.hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.nbits;
+            let mask = 1 << (bit % 64);
+            let prev = self.bits[(bit / 64) as usize].fetch_or(mask, Ordering::Relaxed);
+            new |= prev & mask == 0;
+        }
+
+        self.lines.fetch_add(1, Ordering::Relaxed);
+        if !new {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        new
+    }
+
+    /// Drop the lines of `chunk` which have already been seen. Lines are
+    /// compared without their `\n`.
+    pub fn dedup_chunk(&self, chunk: Vec<u8>) -> Vec<u8> {
+        let mut out = Vec::with_capacity(chunk.len());
+
+        for line in chunk.split_inclusive(|&c| c == b'\n') {
+            let key = line.strip_suffix(b"\n").unwrap_or(line);
+            if self.insert(key) {
+                out.extend_from_slice(line);
+            }
+        }
+
+        out
+    }
+
+    /// Number of lines dropped as duplicates so far, including false
+    /// positives.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Estimated number of true duplicates seen so far, from the number of
+    /// distinct lines implied by how full the filter is.
+    pub fn estimated_duplicates(&self) -> u64 {
+        let set: u64 = self
+            .bits
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_ones() as u64)
+            .sum();
+        let m = self.nbits as f64;
+        let distinct = -m / self.hashes as f64 * (1.0 - set.min(self.nbits - 1) as f64 / m).ln();
+
+        self.lines
+            .load(Ordering::Relaxed)
+            .saturating_sub(distinct.round() as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_dedup() {
+        let dedup = BloomDedup::new(100, 0.001);
+        let out = dedup.dedup_chunk(b"a\nb\na\nc\nb\nc".to_vec());
+        assert_eq!(out, b"a\nb\nc\n");
+        assert_eq!(dedup.dropped(), 3);
+        assert_eq!(dedup.estimated_duplicates(), 3);
+    }
+
+    #[test]
+    fn test_dedup_par() {
+        // Two "files" with 50k lines each, half of them shared.
+        let a: String = (0..50_000).map(|i| format!("{i}\n")).collect();
+        let b: String = (25_000..75_000).map(|i| format!("{i}\n")).collect();
+        let dedup = BloomDedup::new(75_000, 0.01);
+
+        let kept: usize = LineChunks::new(4096, a.as_bytes())
+            .chain(LineChunks::new(4096, b.as_bytes()))
+            .par_bridge()
+            .map(|chunk| dedup.dedup_chunk(chunk.unwrap()))
+            .map(|chunk| chunk.iter().filter(|&&c| c == b'\n').count())
+            .sum();
+
+        // Allow for false positives, and for racing inserts of the same line
+        assert!((74_000..=75_100).contains(&kept), "kept {kept}");
+        let est = dedup.estimated_duplicates();
+        assert!((24_000..26_000).contains(&est), "estimated {est}");
+    }
+}
@@ -0,0 +1,37 @@
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The byte which ends a line for [`LineChunks`](crate::LineChunks).
+///
+/// This is a sealed trait. [`Byte`] fixes the delimiter at compile time, so
+/// the searches for it are specialized to that byte. A plain `u8` is a
+/// delimiter chosen at runtime.
+pub trait Delimiter: sealed::Sealed {
+    fn byte(&self) -> u8;
+}
+
+/// A delimiter fixed at compile time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Byte<const B: u8>;
+
+/// The default `\n` delimiter.
+pub type Newline = Byte<b'\n'>;
+
+impl<const B: u8> sealed::Sealed for Byte<B> {}
+
+impl<const B: u8> Delimiter for Byte<B> {
+    #[inline]
+    fn byte(&self) -> u8 {
+        B
+    }
+}
+
+impl sealed::Sealed for u8 {}
+
+impl Delimiter for u8 {
+    #[inline]
+    fn byte(&self) -> u8 {
+        *self
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Read},
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::Path,
+};
+
+use crate::LineChunks;
+
+// Alignment of buffers, read sizes and offsets for direct IO. This covers
+// the logical block size of common devices.
+const ALIGN: usize = 4096;
+
+/// A file opened for direct IO with `O_DIRECT`, bypassing the page cache,
+/// for [`LineChunks::open_direct`].
+///
+/// Direct IO needs reads into aligned buffers, of aligned sizes at aligned
+/// offsets, so this reads into its own aligned buffer, and the chunker reads
+/// from that as a [`BufRead`]. The last read of a file whose length isn't a
+/// multiple of the block size is short, and after that `O_DIRECT` is turned
+/// off, so a tail which appears later can still be read. Not all filesystems
+/// support direct IO; opening a file on one which doesn't fails.
+pub struct DirectFile {
+    file: File,
+    // `buf[start..start + cap]` is the aligned part of the allocation, of
+    // which `pos..filled` hasn't been consumed yet.
+    buf: Vec<u8>,
+    start: usize,
+    cap: usize,
+    pos: usize,
+    filled: usize,
+    direct: bool,
+}
+
+impl DirectFile {
+    /// Open `path` for direct IO, reading about `bufsize` bytes at a time.
+    pub fn open<P: AsRef<Path>>(path: P, bufsize: usize) -> io::Result<Self> {
+        let file = File::options()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)?;
+
+        let cap = bufsize.max(1).next_multiple_of(ALIGN);
+        let buf = vec![0; cap + ALIGN];
+        let start = buf.as_ptr().align_offset(ALIGN);
+        Ok(DirectFile {
+            file,
+            buf,
+            start,
+            cap,
+            pos: 0,
+            filled: 0,
+            direct: true,
+        })
+    }
+
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    fn buffered(&mut self) -> io::Result<()> {
+        let fd = self.file.as_raw_fd();
+        // SAFETY: the descriptor is open for as long as we own `file`, and
+        // only its status flags are changed.
+        let res = unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags < 0 {
+                flags
+            } else {
+                libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_DIRECT)
+            }
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        self.direct = false;
+        Ok(())
+    }
+}
+
+impl Read for DirectFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill_buf()?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for DirectFile {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.filled {
+            let buf = &mut self.buf[self.start..self.start + self.cap];
+            let len = self.file.read(buf)?;
+            // A short read leaves the file position unaligned, so later
+            // reads can't be direct.
+            if self.direct && len < self.cap {
+                self.buffered()?;
+            }
+            self.pos = 0;
+            self.filled = len;
+        }
+        Ok(&self.buf[self.start + self.pos..self.start + self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+impl LineChunks<DirectFile> {
+    /// Open `path` for direct IO, which bypasses the page cache, and chunk
+    /// it. This is useful for benchmarking with a cold cache, and for large
+    /// batch scans which would otherwise evict everything else from the
+    /// cache. Reads are of `chunksize` rounded up to the block size.
+    pub fn open_direct<P: AsRef<Path>>(chunksize: usize, path: P) -> io::Result<Self> {
+        Ok(LineChunks::from_buf_read(
+            chunksize,
+            DirectFile::open(path, chunksize)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn test_open_direct() {
+        // Not a multiple of the block size, so there's a short tail.
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        let path = env::temp_dir().join(format!("linechunks-direct-{}", process::id()));
+        fs::write(&path, &input).unwrap();
+
+        let chunks = match LineChunks::open_direct(1000, &path) {
+            Ok(chunks) => chunks,
+            // This filesystem doesn't support direct IO.
+            Err(err) if err.kind() == io::ErrorKind::InvalidInput => return,
+            Err(err) => panic!("{err}"),
+        };
+        let chunks: Vec<Vec<u8>> = chunks.map(Result::unwrap).collect();
+        assert!(chunks.iter().all(|chunk| chunk.ends_with(b"\n")));
+        assert_eq!(chunks.concat(), input.as_bytes());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use std::{error::Error, fmt, io};
+
+use crate::FollowEvent;
+
+/// Errors from chunking, for matching on programmatically.
+///
+/// For compatibility with `io::Result` code, chunkers still return
+/// [`io::Error`]s. Errors which didn't come from the reader carry a
+/// `LineChunksError` inside them, which converting back with `From` recovers.
+/// A maximum line length error has [`io::ErrorKind::InvalidData`] as its
+/// kind, since the input isn't the shape it was expected to be, so it isn't
+/// mistaken for a real allocation failure even without converting it.
+///
+/// ```
+/// use linechunks::{LineChunks, LineChunksError};
+///
+/// let mut chunks = LineChunks::new(4, &b"ok\nmuch too long\n"[..]);
+/// chunks.max_line(8);
+/// let err = chunks.find_map(Result::err).unwrap();
+/// match LineChunksError::from(err) {
+///     LineChunksError::MaxLineExceeded { offset, .. } => assert_eq!(offset, 3),
+///     err => panic!("unexpected {err}"),
+/// }
+/// ```
+#[non_exhaustive]
+pub enum LineChunksError {
+    /// An error from the underlying reader.
+    Io(io::Error),
+    /// A line was longer than the maximum line length.
+    MaxLineExceeded {
+        /// Byte offset of the start of the line.
+        offset: u64,
+        /// How much of the line had been read.
+        accumulated: usize,
+        /// The start of the line, which was dropped.
+        partial: Vec<u8>,
+    },
+    /// A followed file was truncated or rotated, from a [`FollowFile`]
+    /// reporting events.
+    ///
+    /// [`FollowFile`]: crate::FollowFile
+    Follow(FollowEvent),
+}
+
+// Bytes of a dropped line to show in debug output. The whole line can be up
+// to `max_line` long.
+const DEBUG_PREFIX: usize = 32;
+
+/// Debug output for the start of a dropped line.
+struct Partial<'a>(&'a [u8]);
+
+impl fmt::Debug for Partial<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = &self.0[..self.0.len().min(DEBUG_PREFIX)];
+        write!(f, "b\"{}\"", prefix.escape_ascii())?;
+        if prefix.len() < self.0.len() {
+            write!(f, "... ({} bytes)", self.0.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LineChunksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineChunksError::Io(err) => f.debug_tuple("Io").field(err).finish(),
+            LineChunksError::MaxLineExceeded {
+                offset,
+                accumulated,
+                partial,
+            } => f
+                .debug_struct("MaxLineExceeded")
+                .field("offset", offset)
+                .field("accumulated", accumulated)
+                .field("partial", &Partial(partial))
+                .finish(),
+            LineChunksError::Follow(event) => f.debug_tuple("Follow").field(event).finish(),
+        }
+    }
+}
+
+impl fmt::Display for LineChunksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineChunksError::Io(err) => err.fmt(f),
+            LineChunksError::MaxLineExceeded {
+                offset,
+                accumulated,
+                ..
+            } => write!(
+                f,
+                "Max line length exceeded: {accumulated} bytes at offset {offset}"
+            ),
+            LineChunksError::Follow(event) => event.fmt(f),
+        }
+    }
+}
+
+impl Error for LineChunksError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LineChunksError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LineChunksError {
+    fn from(err: io::Error) -> Self {
+        if err
+            .get_ref()
+            .is_some_and(|inner| inner.is::<LineChunksError>())
+        {
+            let inner = err.into_inner().expect("missing inner error");
+            *inner.downcast().expect("inner error changed type")
+        } else {
+            LineChunksError::Io(err)
+        }
+    }
+}
+
+impl From<LineChunksError> for io::Error {
+    fn from(err: LineChunksError) -> Self {
+        match err {
+            LineChunksError::Io(err) => err,
+            err @ LineChunksError::MaxLineExceeded { .. } => {
+                io::Error::new(io::ErrorKind::InvalidData, err)
+            }
+            err @ LineChunksError::Follow(_) => io::Error::other(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let err = io::Error::from(LineChunksError::MaxLineExceeded {
+            offset: 10,
+            accumulated: 100,
+            partial: vec![b'x'; 100],
+        });
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(
+            LineChunksError::from(err),
+            LineChunksError::MaxLineExceeded {
+                offset: 10,
+                accumulated: 100,
+                ..
+            }
+        ));
+
+        let err = LineChunksError::from(io::Error::from(io::ErrorKind::OutOfMemory));
+        assert!(matches!(err, LineChunksError::Io(_)));
+    }
+
+    #[test]
+    fn test_debug() {
+        let err = LineChunksError::MaxLineExceeded {
+            offset: 10,
+            accumulated: 1 << 20,
+            partial: vec![b'x'; 1 << 20],
+        };
+        let debug = format!("{:?}", io::Error::from(err));
+        assert!(debug.len() < 200, "{debug}");
+        assert!(debug.contains("... (1048576 bytes)"), "{debug}");
+
+        let err = LineChunksError::MaxLineExceeded {
+            offset: 0,
+            accumulated: 3,
+            partial: b"a\nb".to_vec(),
+        };
+        assert_eq!(
+            format!("{err:?}"),
+            r#"MaxLineExceeded { offset: 0, accumulated: 3, partial: b"a\nb" }"#
+        );
+    }
+}
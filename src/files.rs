@@ -0,0 +1,165 @@
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+};
+
+use rayon::prelude::*;
+
+use crate::{shutdown::Shutdown, split::split_ranges, LineChunks};
+
+/// Size of the byte ranges large files are split into, in chunks.
+const SPLIT_CHUNKS: u64 = 64;
+
+/// Plan the byte ranges to read a file in. `None` means reading the whole file
+/// in one go, for files which can't be sized and seeked, such as pipes or
+/// files on WASI hosts without seek support.
+fn plan(path: &Path, split: u64) -> io::Result<Vec<Option<Range<u64>>>> {
+    // Check the path's metadata before opening, since opening a pipe blocks
+    // and closing it again would lose data.
+    let len = match fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        Ok(_) => return Ok(vec![None]),
+        Err(err) if err.kind() == ErrorKind::Unsupported => return Ok(vec![None]),
+        Err(err) => return Err(err),
+    };
+
+    let mut file = File::open(path)?;
+    match split_ranges(&mut file, len, split) {
+        Ok(ranges) => Ok(ranges.into_iter().map(Some).collect()),
+        Err(err) if err.kind() == ErrorKind::Unsupported => Ok(vec![None]),
+        Err(err) => Err(err),
+    }
+}
+
+/// Process the chunks of many files in parallel.
+///
+/// Calls `f` with each line-aligned chunk of each file, along with the file's
+/// path. Work is balanced across both files and within them: files larger
+/// than 64 chunks are split into line-aligned byte ranges of about that size,
+/// and all the files and ranges are scheduled together on the rayon pool.
+/// So one huge file among many tiny ones still keeps every thread busy.
+/// Files which can't be seeked, such as named pipes, are read whole.
+///
+/// Chunks never span files, and the chunks of a file are only processed in
+/// order within one range. Returns the first error from opening or reading a
+/// file, or from `f`. If `f` panics, no further chunks are read and the panic
+/// is propagated.
+pub fn for_each_file_chunk<P, F>(paths: &[P], chunksize: usize, f: F) -> io::Result<()>
+where
+    P: AsRef<Path> + Sync,
+    F: Fn(&Path, Vec<u8>) -> io::Result<()> + Sync,
+{
+    let split = chunksize as u64 * SPLIT_CHUNKS;
+    let shutdown = Shutdown::default();
+
+    let work = paths
+        .par_iter()
+        .map(|path| {
+            let path = path.as_ref();
+            let ranges = plan(path, split)?;
+
+            Ok(ranges.into_iter().map(move |range| (path, range)))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    work.into_par_iter()
+        .flat_map_iter(|ranges| ranges)
+        .try_for_each(|(path, range)| {
+            if shutdown.is_stopped() {
+                return Ok(());
+            }
+
+            let mut file = File::open(path)?;
+            let len = match range {
+                Some(range) => {
+                    file.seek(SeekFrom::Start(range.start))?;
+                    range.end - range.start
+                }
+                None => u64::MAX,
+            };
+            let chunks = LineChunks::new(chunksize, file.take(len));
+
+            for chunk in shutdown.source(chunks) {
+                let _guard = shutdown.guard();
+                f(path, chunk?)?;
+            }
+            Ok(())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        env, fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        let dir = env::temp_dir().join(format!("linechunks-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut paths = Vec::new();
+        let big: String = (0..100_000).map(|i| format!("big {i}\n")).collect();
+        paths.push(dir.join("big"));
+        fs::write(&paths[0], &big).unwrap();
+        for i in 0..50 {
+            let path = dir.join(format!("small{i}"));
+            fs::write(&path, format!("small {i}\nlast {i}")).unwrap();
+            paths.push(path);
+        }
+        paths.push(dir.join("empty"));
+        fs::write(&paths[51], "").unwrap();
+
+        let lines = AtomicUsize::new(0);
+        for_each_file_chunk(&paths, 1024, |path, chunk| {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+            let text = String::from_utf8(chunk).unwrap();
+
+            for line in text.lines() {
+                assert!(
+                    line.starts_with(prefix) || line.starts_with("last"),
+                    "{line}"
+                );
+                lines.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(lines.into_inner(), 100_100);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo() {
+        let path = env::temp_dir().join(format!("linechunks-fifo-{}", std::process::id()));
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || fs::write(path, "a\nb\nc\n").unwrap()
+        });
+
+        let data = std::sync::Mutex::new(Vec::new());
+        for_each_file_chunk(&[&path], 1024, |_, chunk| {
+            data.lock().unwrap().extend(chunk);
+            Ok(())
+        })
+        .unwrap();
+
+        writer.join().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(data.into_inner().unwrap(), b"a\nb\nc\n");
+    }
+}
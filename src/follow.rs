@@ -0,0 +1,197 @@
+use std::{
+    fmt,
+    fs::{self, File, Metadata},
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::{LineChunks, LineChunksError};
+
+/// Something which happened to a file followed by a [`FollowFile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FollowEvent {
+    /// The file shrank, so it's read again from the start.
+    Truncated,
+    /// The path was replaced by a new file, which is read from the start.
+    Rotated,
+}
+
+impl fmt::Display for FollowEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FollowEvent::Truncated => write!(f, "followed file was truncated"),
+            FollowEvent::Rotated => write!(f, "followed file was rotated"),
+        }
+    }
+}
+
+/// A file being followed by path, for [`LineChunks::follow_path`].
+///
+/// At the end of the file it checks whether the file has been truncated or
+/// the path rotated to a new file, as `logrotate` does, and if so carries on
+/// reading from the start of the file now there. Rotation is detected by
+/// inode, so only on Unix.
+///
+/// By default this happens silently. With
+/// [`report_events`](Self::report_events) a read returns the
+/// [`FollowEvent`] as an error carrying a [`LineChunksError::Follow`], after
+/// which reading continues. A partial line at the end of the old file is
+/// joined to the start of the new one either way.
+pub struct FollowFile {
+    path: PathBuf,
+    file: File,
+    id: Option<(u64, u64)>,
+    // Offset in the current file.
+    pos: u64,
+    report: bool,
+}
+
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl FollowFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FollowFile> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let id = file_id(&file.metadata()?);
+
+        Ok(FollowFile {
+            path: path.to_path_buf(),
+            file,
+            id,
+            pos: 0,
+            report: false,
+        })
+    }
+
+    /// Return truncation and rotation as errors, so the caller can decide
+    /// what to do. Use this with [`LineChunks::recoverable`] to keep reading
+    /// afterwards.
+    pub fn report_events(&mut self, report: bool) {
+        self.report = report;
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn check(&self) -> io::Result<Option<FollowEvent>> {
+        if self.file.metadata()?.len() < self.pos {
+            return Ok(Some(FollowEvent::Truncated));
+        }
+
+        // A missing path is a rotation which hasn't finished yet.
+        match fs::metadata(&self.path) {
+            Ok(meta) if self.id.is_some() && file_id(&meta) != self.id => {
+                Ok(Some(FollowEvent::Rotated))
+            }
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+            _ => Ok(None),
+        }
+    }
+
+    fn reopen(&mut self, event: FollowEvent) -> io::Result<()> {
+        match event {
+            FollowEvent::Truncated => {
+                self.file.seek(SeekFrom::Start(0))?;
+            }
+            FollowEvent::Rotated => {
+                self.file = File::open(&self.path)?;
+                self.id = file_id(&self.file.metadata()?);
+            }
+        }
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl Read for FollowFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let len = self.file.read(buf)?;
+            self.pos += len as u64;
+            if len > 0 || buf.is_empty() {
+                return Ok(len);
+            }
+
+            match self.check()? {
+                None => return Ok(0),
+                Some(event) => {
+                    self.reopen(event)?;
+                    if self.report {
+                        return Err(LineChunksError::Follow(event).into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl LineChunks<FollowFile> {
+    /// Open `path` and [`follow`](LineChunks::follow) it, like `tail -F`,
+    /// carrying on through truncation and rotation. The
+    /// [`FollowFile`] is available from [`get_mut`](LineChunks::get_mut) to
+    /// configure it.
+    pub fn follow_path<P: AsRef<Path>>(chunksize: usize, path: P) -> io::Result<Self> {
+        let mut chunks = LineChunks::new(chunksize, FollowFile::open(path)?);
+        chunks.follow(true);
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_follow_path() {
+        let path = env::temp_dir().join(format!("linechunks-{}-follow", process::id()));
+        let rotated = path.with_extension("1");
+        fs::write(&path, "a\nb\n").unwrap();
+
+        let mut chunks = LineChunks::follow_path(64, &path).unwrap();
+        chunks.get_mut().report_events(true);
+        chunks.recoverable(true);
+        chunks.follow_notify({
+            let (path, rotated) = (path.clone(), rotated.clone());
+            let mut waits = 0;
+            move || {
+                waits += 1;
+                match waits {
+                    1 => fs::write(&path, "c\n"),
+                    2 => {
+                        fs::rename(&path, &rotated)?;
+                        fs::write(&path, "d\n")
+                    }
+                    _ => Err(io::Error::other("stop")),
+                }
+            }
+        });
+
+        let mut next = || match chunks.next().unwrap() {
+            Ok(chunk) => String::from_utf8(chunk).unwrap(),
+            Err(err) => err.to_string(),
+        };
+        assert_eq!(next(), "a\nb\n");
+        assert_eq!(next(), "followed file was truncated");
+        assert_eq!(next(), "c\n");
+        assert_eq!(next(), "followed file was rotated");
+        assert_eq!(next(), "d\n");
+        assert_eq!(next(), "stop");
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&rotated).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    io::{self, Write},
+    mem,
+};
+
+use flate2::{write::GzEncoder, Compression};
+use rayon::prelude::*;
+
+/// Write chunks as gzip, compressing them in parallel.
+///
+/// Like `pigz`, each chunk is compressed independently on the rayon pool as
+/// its own gzip member, and the members are written out in order. A
+/// multi-member gzip file is valid, and decompresses to the concatenation of
+/// the chunks. Chunks are compressed in batches of a few per thread, so
+/// memory use is bounded.
+///
+/// Each member has some overhead and compression doesn't carry across
+/// chunks, so chunks should be reasonably large (say 64KB or more).
+pub struct ParallelGzWriter<W: Write> {
+    out: W,
+    level: Compression,
+    batch: Vec<Vec<u8>>,
+    batch_size: usize,
+}
+
+impl<W: Write> ParallelGzWriter<W> {
+    pub fn new(out: W) -> Self {
+        let batch_size = rayon::current_num_threads() * 2;
+        ParallelGzWriter {
+            out,
+            level: Compression::default(),
+            batch: Vec::with_capacity(batch_size),
+            batch_size,
+        }
+    }
+
+    /// Compression level from 0 to 9. Defaults to 6.
+    pub fn level(&mut self, level: u32) {
+        self.level = Compression::new(level);
+    }
+
+    /// Compress and write the current batch.
+    fn flush_batch(&mut self) -> io::Result<()> {
+        let batch = mem::take(&mut self.batch);
+        let level = self.level;
+
+        let members = batch
+            .into_par_iter()
+            .map(|chunk| {
+                let mut enc = GzEncoder::new(Vec::with_capacity(chunk.len() / 2), level);
+                enc.write_all(&chunk)?;
+                enc.finish()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        for member in members {
+            self.out.write_all(&member)?;
+        }
+        Ok(())
+    }
+
+    /// Add a chunk to the output.
+    pub fn write_chunk(&mut self, chunk: Vec<u8>) -> io::Result<()> {
+        if !chunk.is_empty() {
+            self.batch.push(chunk);
+        }
+        if self.batch.len() >= self.batch_size {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Write any remaining chunks and return the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_batch()?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use flate2::read::MultiGzDecoder;
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_parallel_gz() {
+        let input: String = (0..100_000).map(|i| format!("line {i}\n")).collect();
+        let mut writer = ParallelGzWriter::new(Vec::new());
+        writer.level(1);
+
+        for chunk in LineChunks::new(16 << 10, input.as_bytes()) {
+            writer.write_chunk(chunk.unwrap()).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+        assert!(compressed.len() < input.len() / 2);
+
+        let mut output = String::new();
+        MultiGzDecoder::new(&compressed[..])
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, input);
+    }
+}
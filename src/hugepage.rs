@@ -0,0 +1,27 @@
+/// Ask the kernel to back the whole pages in `buf` with transparent
+/// hugepages. This is only a hint, so failure is ignored.
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+pub(crate) fn advise(buf: *const u8, len: usize) {
+    // SAFETY: sysconf has no preconditions.
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let start = (buf as usize).next_multiple_of(page);
+    let end = (buf as usize + len) / page * page;
+
+    if end > start {
+        // SAFETY: the range is page-aligned and lies within `buf`, which we
+        // own. MADV_HUGEPAGE doesn't change the contents of memory.
+        unsafe { libc::madvise(start as *mut libc::c_void, end - start, libc::MADV_HUGEPAGE) };
+    }
+}
+
+#[cfg(not(all(feature = "hugepages", target_os = "linux")))]
+pub(crate) fn advise(_buf: *const u8, _len: usize) {}
+
+/// Allocate a buffer, advising hugepages for it if `hugepages` is set.
+pub(crate) fn alloc(capacity: usize, hugepages: bool) -> Vec<u8> {
+    let buf = Vec::with_capacity(capacity);
+    if hugepages {
+        advise(buf.as_ptr(), buf.capacity());
+    }
+    buf
+}
@@ -0,0 +1,234 @@
+use std::{
+    io::{self, BufReader, ErrorKind, Read, Write},
+    ops::Range,
+};
+
+const MAGIC: &[u8; 8] = b"LCINDEX1";
+// Most line starts to preallocate for when loading.
+const MAX_PREALLOC: u64 = 1 << 16;
+// Size of reads when building an index.
+const BUF_SIZE: usize = 1 << 16;
+
+/// The byte offset of the start of every line in a file.
+///
+/// With an index, any line or range of lines can be found without scanning.
+/// Indexes can be saved and loaded again later, in a compact format which
+/// stores the line lengths as varints, so a large file only needs to be
+/// scanned once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    starts: Vec<u64>,
+    len: u64,
+}
+
+fn write_varint<W: Write>(w: &mut W, mut v: u64) -> io::Result<()> {
+    let mut buf = [0; 10];
+    let mut i = 0;
+    while v >= 0x80 {
+        buf[i] = v as u8 | 0x80;
+        v >>= 7;
+        i += 1;
+    }
+    buf[i] = v as u8;
+    w.write_all(&buf[..=i])
+}
+
+fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut v = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        v |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(io::Error::new(
+        ErrorKind::InvalidData,
+        "bad varint in line index",
+    ))
+}
+
+impl LineIndex {
+    /// Build an index by reading all of `reader`. Lines can be any length.
+    pub fn build<R: Read>(mut reader: R) -> io::Result<LineIndex> {
+        let mut starts = vec![0];
+        let mut len = 0;
+        // Only `\n`s are counted, so there's no need to chunk by line.
+        let mut buf = vec![0; BUF_SIZE];
+
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            starts.extend(memchr::memchr_iter(b'\n', &buf[..read]).map(|eol| len + eol as u64 + 1));
+            len += read as u64;
+        }
+        // A final \n (or an empty file) doesn't start a line.
+        if starts.last() == Some(&len) {
+            starts.pop();
+        }
+
+        Ok(LineIndex { starts, len })
+    }
+
+    /// Number of lines, counting an unterminated final line.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// True if the file was empty.
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Length of the indexed file in bytes. This can be used to check whether
+    /// a loaded index is stale.
+    pub fn file_len(&self) -> u64 {
+        self.len
+    }
+
+    /// Byte range of lines `lines`, including their `\n`s. Returns `None` if
+    /// the range is out of bounds.
+    pub fn range(&self, lines: Range<usize>) -> Option<Range<u64>> {
+        if lines.start > lines.end || lines.end > self.len() {
+            return None;
+        }
+        let start = self.starts.get(lines.start).copied().unwrap_or(self.len);
+        let end = self.starts.get(lines.end).copied().unwrap_or(self.len);
+        Some(start..end)
+    }
+
+    /// Byte range of line `line` (counting from 0), including its `\n`.
+    pub fn line(&self, line: usize) -> Option<Range<u64>> {
+        self.range(line..line.checked_add(1)?)
+    }
+
+    /// Save the index to `w`.
+    pub fn save<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut w = io::BufWriter::new(w);
+        w.write_all(MAGIC)?;
+        write_varint(&mut w, self.len)?;
+        write_varint(&mut w, self.starts.len() as u64)?;
+
+        let mut prev = 0;
+        for &start in &self.starts {
+            write_varint(&mut w, start - prev)?;
+            prev = start;
+        }
+        w.flush()
+    }
+
+    /// Load an index saved with [`save`](Self::save).
+    pub fn load<R: Read>(r: R) -> io::Result<LineIndex> {
+        let mut r = BufReader::new(r);
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a line index"));
+        }
+
+        let len = read_varint(&mut r)?;
+        let count = read_varint(&mut r)?;
+        // Don't trust the header with a large allocation; a bad count fails
+        // once the input runs out.
+        let mut starts = Vec::with_capacity(count.min(len).min(MAX_PREALLOC) as usize);
+        // The first line starts at 0, and the rest strictly increase
+        // within the file, so every range is in bounds.
+        let mut start = 0u64;
+        for i in 0..count {
+            let delta = read_varint(&mut r)?;
+            start = start
+                .checked_add(delta)
+                .filter(|&start| start < len && (delta > 0) == (i > 0))
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "bad line index"))?;
+            starts.push(start);
+        }
+
+        Ok(LineIndex { starts, len })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_index() {
+        let input: String = (0..10_000).map(|i| format!("line {i}\n")).collect();
+        let index = LineIndex::build(input.as_bytes()).unwrap();
+
+        assert_eq!(index.len(), 10_000);
+        let line = index.line(1234).unwrap();
+        assert_eq!(
+            &input[line.start as usize..line.end as usize],
+            "line 1234\n"
+        );
+        let range = index.range(10..12).unwrap();
+        assert_eq!(
+            &input[range.start as usize..range.end as usize],
+            "line 10\nline 11\n"
+        );
+        assert_eq!(
+            index.range(10_000..10_000),
+            Some(input.len() as u64..input.len() as u64)
+        );
+        assert_eq!(index.line(10_000), None);
+
+        let unterminated = LineIndex::build(&b"a\n\nb"[..]).unwrap();
+        assert_eq!(unterminated.len(), 3);
+        assert_eq!(unterminated.line(2), Some(3..4));
+        assert!(LineIndex::build(&b""[..]).unwrap().is_empty());
+
+        // Lines longer than a chunker's default max_line are fine.
+        let long = format!("a\n{}\nb\n", "x".repeat(3 << 20));
+        let index = LineIndex::build(long.as_bytes()).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.line(2),
+            Some(long.len() as u64 - 2..long.len() as u64)
+        );
+    }
+
+    #[test]
+    fn test_save_load() {
+        let input: String = (0..10_000).map(|i| format!("line {i}\n")).collect();
+        let index = LineIndex::build(input.as_bytes()).unwrap();
+
+        let mut saved = Vec::new();
+        index.save(&mut saved).unwrap();
+        assert!(saved.len() < input.len() / 4);
+        assert_eq!(LineIndex::load(&saved[..]).unwrap(), index);
+
+        // A huge count in the header is an error, not a huge allocation.
+        let mut hostile = MAGIC.to_vec();
+        write_varint(&mut hostile, u64::MAX).unwrap();
+        write_varint(&mut hostile, u64::MAX).unwrap();
+        assert!(LineIndex::load(&hostile[..]).is_err());
+        assert_eq!(index.line(usize::MAX), None);
+
+        // Offsets must start at 0, strictly increase and be within the file.
+        for deltas in [&[1, 2][..], &[0, 2, 0], &[0, 5, 6]] {
+            let mut bad = MAGIC.to_vec();
+            write_varint(&mut bad, 10).unwrap();
+            write_varint(&mut bad, deltas.len() as u64).unwrap();
+            for &delta in deltas {
+                write_varint(&mut bad, delta).unwrap();
+            }
+            assert_eq!(
+                LineIndex::load(&bad[..]).unwrap_err().kind(),
+                ErrorKind::InvalidData,
+                "{deltas:?}"
+            );
+        }
+
+        saved[0] = b'x';
+        assert_eq!(
+            LineIndex::load(&saved[..]).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
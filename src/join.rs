@@ -0,0 +1,257 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use crate::lines::ChunkLines;
+
+/// Which records a [`Join`] yields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Only lines with a matching key on both sides.
+    Inner,
+    /// Every left line, with the matching right lines if there are any.
+    Left,
+    /// Every line from both sides, matched up where possible.
+    Outer,
+}
+
+/// A record yielded by [`Join`]. For an inner join both sides are always
+/// present.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinRecord {
+    pub left: Option<Vec<u8>>,
+    pub right: Option<Vec<u8>>,
+}
+
+/// Sort-merge join of two sorted streams of line-aligned chunks.
+///
+/// Each side has a key extractor, and both sides must be sorted bytewise by
+/// their key. Lines with equal keys are joined pairwise. Lines are yielded
+/// without their `\n`, and empty lines are skipped.
+///
+/// Both inputs are streamed. Memory use is bounded by the largest group of
+/// right-side lines sharing a key, which are buffered to be joined with each
+/// matching left-side line.
+pub struct Join<A, B, KA, KB> {
+    left: ChunkLines<A>,
+    right: ChunkLines<B>,
+    left_key: KA,
+    right_key: KB,
+    kind: JoinKind,
+    finished: bool,
+    // Lookahead on each side
+    l: Option<Vec<u8>>,
+    r: Option<Vec<u8>>,
+    // Right lines with key `group_key`
+    group: Vec<Vec<u8>>,
+    group_key: Vec<u8>,
+    pending: VecDeque<JoinRecord>,
+}
+
+impl<A, B, KA, KB, E> Join<A, B, KA, KB>
+where
+    A: Iterator<Item = Result<Vec<u8>, E>>,
+    B: Iterator<Item = Result<Vec<u8>, E>>,
+    KA: Fn(&[u8]) -> &[u8],
+    KB: Fn(&[u8]) -> &[u8],
+{
+    pub fn new(left: A, right: B, left_key: KA, right_key: KB, kind: JoinKind) -> Self {
+        Join {
+            left: ChunkLines::new(left),
+            right: ChunkLines::new(right),
+            left_key,
+            right_key,
+            kind,
+            finished: false,
+            l: None,
+            r: None,
+            group: Vec::new(),
+            group_key: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<()>, E> {
+        if self.l.is_none() {
+            self.l = self.left.next().transpose()?;
+        }
+
+        // Join with the buffered right group while the left key matches
+        if let Some(l) = &self.l {
+            if !self.group.is_empty() {
+                if (self.left_key)(l) == self.group_key {
+                    let l = self.l.take();
+                    for r in &self.group {
+                        self.pending.push_back(JoinRecord {
+                            left: l.clone(),
+                            right: Some(r.clone()),
+                        });
+                    }
+                    return Ok(Some(()));
+                }
+                self.group.clear();
+            }
+        }
+
+        if self.r.is_none() {
+            self.r = self.right.next().transpose()?;
+        }
+
+        let ord = match (&self.l, &self.r) {
+            (None, None) => return Ok(None),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(l), Some(r)) => (self.left_key)(l).cmp((self.right_key)(r)),
+        };
+
+        match ord {
+            Ordering::Less => {
+                let left = self.l.take();
+                if self.kind == JoinKind::Inner && self.r.is_none() {
+                    // Nothing left to match against
+                    return Ok(None);
+                }
+                if self.kind != JoinKind::Inner {
+                    self.pending.push_back(JoinRecord { left, right: None });
+                }
+            }
+            Ordering::Greater => {
+                let right = self.r.take();
+                if self.kind != JoinKind::Outer && self.l.is_none() {
+                    return Ok(None);
+                }
+                if self.kind == JoinKind::Outer {
+                    self.pending.push_back(JoinRecord { left: None, right });
+                }
+            }
+            Ordering::Equal => {
+                // Gather all the right lines with this key
+                let r = self.r.take().expect("right line missing");
+                self.group_key = (self.right_key)(&r).to_vec();
+                self.group.push(r);
+
+                loop {
+                    self.r = self.right.next().transpose()?;
+                    match &self.r {
+                        Some(r) if (self.right_key)(r) == self.group_key => {
+                            self.group.push(self.r.take().expect("right line missing"));
+                        }
+                        _ => break,
+                    }
+                }
+            }
+        }
+
+        Ok(Some(()))
+    }
+}
+
+impl<A, B, KA, KB, E> Iterator for Join<A, B, KA, KB>
+where
+    A: Iterator<Item = Result<Vec<u8>, E>>,
+    B: Iterator<Item = Result<Vec<u8>, E>>,
+    KA: Fn(&[u8]) -> &[u8],
+    KB: Fn(&[u8]) -> &[u8],
+{
+    type Item = Result<JoinRecord, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                break Some(Ok(record));
+            }
+            if self.finished {
+                break None;
+            }
+
+            match self.step() {
+                Ok(Some(())) => {}
+                Ok(None) => self.finished = true,
+                Err(err) => {
+                    self.finished = true;
+                    break Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    fn field(line: &[u8]) -> &[u8] {
+        line.split(|&c| c == b' ').next().unwrap()
+    }
+
+    fn join(kind: JoinKind) -> Vec<(String, String)> {
+        let left = &b"a l1\nb l2\nb l3\nd l4\n"[..];
+        let right = &b"b r1\nb r2\nc r3\nd r4\ne r5\n"[..];
+
+        Join::new(
+            LineChunks::new(4, left),
+            LineChunks::new(4, right),
+            field,
+            field,
+            kind,
+        )
+        .map(|record| {
+            let record = record.unwrap();
+            let show = |side: Option<Vec<u8>>| String::from_utf8(side.unwrap_or_default()).unwrap();
+            (show(record.left), show(record.right))
+        })
+        .collect()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_inner() {
+        assert_eq!(
+            join(JoinKind::Inner),
+            pairs(&[
+                ("b l2", "b r1"),
+                ("b l2", "b r2"),
+                ("b l3", "b r1"),
+                ("b l3", "b r2"),
+                ("d l4", "d r4"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_left() {
+        assert_eq!(
+            join(JoinKind::Left),
+            pairs(&[
+                ("a l1", ""),
+                ("b l2", "b r1"),
+                ("b l2", "b r2"),
+                ("b l3", "b r1"),
+                ("b l3", "b r2"),
+                ("d l4", "d r4"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_outer() {
+        assert_eq!(
+            join(JoinKind::Outer),
+            pairs(&[
+                ("a l1", ""),
+                ("b l2", "b r1"),
+                ("b l2", "b r2"),
+                ("b l3", "b r1"),
+                ("b l3", "b r2"),
+                ("", "c r3"),
+                ("d l4", "d r4"),
+                ("", "e r5"),
+            ])
+        );
+    }
+}
@@ -1,8 +1,160 @@
 use std::{
-    io::{self, BufRead, BufReader, ErrorKind, Read},
+    fs::File,
+    io::{self, BufRead, ErrorKind, Read, Seek},
     mem,
+    ops::Range,
+    path::Path,
+    thread,
+    time::Duration,
 };
 
+#[cfg(feature = "rayon")]
+mod agg;
+#[cfg(feature = "tokio")]
+mod aio;
+#[cfg(feature = "zip")]
+mod archive;
+mod borrow;
+mod budget;
+mod builder;
+mod cdc;
+mod chain;
+#[cfg(feature = "charset")]
+mod charset;
+mod check;
+mod comm;
+mod config;
+mod dedup;
+mod delim;
+#[cfg(all(feature = "direct-io", target_os = "linux"))]
+mod direct;
+mod error;
+#[cfg(feature = "rayon")]
+mod files;
+mod follow;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod hugepage;
+mod index;
+mod join;
+mod linefile;
+mod lines;
+mod lookup;
+mod map;
+mod message;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod newline;
+mod offsets;
+#[cfg(feature = "rayon")]
+mod ordered;
+mod parallel;
+mod pipeline;
+#[cfg(feature = "rayon")]
+mod plan;
+mod policy;
+mod pool;
+mod pos;
+mod prefetch;
+mod readahead;
+mod record;
+mod redact;
+mod rev;
+mod sample;
+mod scoped;
+mod select;
+#[cfg(feature = "bytes")]
+mod shared;
+mod shutdown;
+mod sink;
+mod slice;
+#[cfg(feature = "rayon")]
+mod sorted;
+mod source;
+mod split;
+mod splitter;
+#[cfg(feature = "rusqlite")]
+mod sqlite;
+mod stash;
+mod tee;
+mod try_parse;
+mod upload;
+mod utf8;
+mod validate;
+mod window;
+
+#[cfg(feature = "rayon")]
+pub use agg::{group_by_key, top_k};
+#[cfg(feature = "tokio")]
+pub use aio::AsyncLineChunks;
+#[cfg(feature = "zip")]
+pub use archive::for_each_zip_chunk;
+pub use borrow::ByRef;
+pub use budget::{Budgeted, Charged, MemoryBudget};
+pub use builder::LineChunksBuilder;
+pub use cdc::CdcChunks;
+pub use chain::ChainChunks;
+#[cfg(feature = "charset")]
+pub use charset::DetectCharset;
+pub use check::{CheckPolicy, CheckSummary, LineCheck};
+pub use comm::{Comm, CommLine};
+pub use config::ChunkConfig;
+pub use dedup::BloomDedup;
+pub use delim::{Byte, Delimiter, Newline};
+#[cfg(all(feature = "direct-io", target_os = "linux"))]
+pub use direct::DirectFile;
+pub use error::LineChunksError;
+#[cfg(feature = "rayon")]
+pub use files::for_each_file_chunk;
+pub use follow::{FollowEvent, FollowFile};
+#[cfg(feature = "gzip")]
+pub use gzip::ParallelGzWriter;
+pub use index::LineIndex;
+pub use join::{Join, JoinKind, JoinRecord};
+pub use linefile::LineFile;
+pub use lines::{ByteLines, Lines, MapLines};
+pub use lookup::{sorted_lookup, sorted_range};
+pub use map::MapChunks;
+pub use message::{Batcher, MessageSink};
+#[cfg(feature = "mmap")]
+pub use mmap::{MmapChunk, MmapLineChunks};
+pub use newline::NormalizeNewlines;
+pub use offsets::{Offsets, WithMetadata};
+#[cfg(feature = "rayon")]
+pub use ordered::ParMapOrdered;
+pub use parallel::ParallelLineChunks;
+pub use pipeline::Pipeline;
+#[cfg(feature = "rayon")]
+pub use plan::{ParChunks, Plan};
+pub use policy::{ErrorPolicy, ErrorReport, OversizePolicy, WithPolicy};
+pub use pool::{BufferPool, Pooled, PooledBuf};
+pub use pos::{LinePos, LineSplitPos};
+pub use prefetch::Prefetch;
+pub use record::RecordChunks;
+pub use redact::Redactor;
+pub use rev::RevLineChunks;
+pub use sample::{Sample, Sampling};
+pub use select::SelectLines;
+#[cfg(feature = "bytes")]
+pub use shared::BytesChunks;
+pub use sink::OrderedSink;
+pub use slice::SliceChunks;
+#[cfg(feature = "rayon")]
+pub use sorted::{verify_sorted, Unsorted};
+pub use split::{plan_splits, split_points};
+#[cfg(feature = "rusqlite")]
+pub use sqlite::{LoadOrder, SqliteSink};
+pub use stash::StashErrors;
+pub use tee::Tee;
+pub use try_parse::{ParseError, TryLineSplitParse};
+pub use upload::{ObjectStore, ShardedUpload};
+pub use validate::{Validate, Violation, ViolationKind};
+pub use window::{Window, Windows};
+
+use policy::ErrorTracker;
+use source::Source;
+use splitter::{Splitter, Trim};
+
 /// Read an unbuffered input into chunks with a guaranteed minimum size
 ///
 /// The chunks are always line-aligned - that is, they always end with a `\n`
@@ -13,12 +165,149 @@ use std::{
 /// Chunks may be larger than chunksize if needed to encompass an entire line.
 /// The max line length is bounded to 32 times the chunk size, to prevent
 /// unbounded memory use for inputs which contain no line breaks.
-pub struct LineChunks<R> {
-    buffer: BufReader<R>,
+///
+/// Lines end with `\n` by default. Use [`with_delimiter`](Self::with_delimiter)
+/// to split on another byte, such as `\0`.
+///
+/// On Windows, a broken pipe error is treated as the end of the input, since
+/// that's how reading from a pipe reports the writer closing it. Other errors
+/// can be treated the same way with [`eof_on`](Self::eof_on).
+pub struct LineChunks<R, D = Newline> {
+    buffer: Source<R>,
+    delim: D,
     finished: bool,
     accum: Vec<u8>,
     max_line: usize,
     min_chunk: usize,
+    // Moving average of line length, if min_chunk or the chunk size is
+    // adaptive, and the bounds of an adaptive chunk size.
+    avg_line: Option<usize>,
+    adaptive_size: Option<(usize, usize)>,
+    errors: ErrorTracker<io::Error>,
+    oversize: OversizePolicy,
+    // Lines handled by the oversize policy, and whether splits avoid
+    // cutting UTF-8 characters.
+    oversized: u64,
+    split_utf8: bool,
+    // Discarding input up to the next `\n` after dropping an over-long line.
+    skip_line: bool,
+    // The last read failed, and we haven't made progress since.
+    read_failed: bool,
+    // Offset of the next byte to be consumed from `buffer`.
+    offset: u64,
+    // Resynchronising after a read error, from this offset.
+    resync: bool,
+    resync_from: Option<u64>,
+    skipped: Vec<Range<u64>>,
+    // Whether the last byte consumed was a `\n`, and its value at EOF.
+    last_newline: bool,
+    ends_with_newline: Option<bool>,
+    hugepages: bool,
+    // Errors don't end iteration.
+    recoverable: bool,
+    // Errors which are treated as the end of the input.
+    eof_errors: Vec<ErrorKind>,
+    // Expected length of the input, for size_hint.
+    len_hint: Option<u64>,
+    // Index of the next chunk, and the observer to tell about it.
+    index: u64,
+    observer: Option<Observer>,
+    // Decides whether to retry a failed read, and the consecutive retries so
+    // far.
+    retry: Option<RetryHook>,
+    retries: u32,
+    // Waiting for more input at EOF rather than finishing, by sleeping for
+    // `poll` or calling `notify`.
+    follow: bool,
+    poll: Duration,
+    notify: Option<Notifier>,
+    // Where to get buffers for new chunks from: a buffer given back by
+    // `next_chunk_into`, or the pool.
+    spare: Option<Vec<u8>>,
+    pool: Option<BufferPool>,
+    // Length of the chunk at the start of `accum` lent by `next_borrowed`.
+    lent: usize,
+}
+
+// Lines per chunk to aim for with an adaptive min_chunk, and with an
+// adaptive chunk size.
+const ADAPTIVE_LINES: usize = 4;
+const ADAPTIVE_SIZE_LINES: usize = 1024;
+
+type Observer = Box<dyn FnMut(&ChunkInfo) + Send>;
+type RetryHook = Box<dyn FnMut(&io::Error, u32) -> bool + Send>;
+type Notifier = Box<dyn FnMut() -> io::Result<()> + Send>;
+
+/// Information about a chunk, passed to a [`LineChunks::on_chunk`] observer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkInfo {
+    /// Index of the chunk, starting at 0.
+    pub index: u64,
+    /// Byte offset of the start of the chunk in the input.
+    pub offset: u64,
+    /// Length of the chunk in bytes.
+    pub len: usize,
+    /// Number of lines in the chunk, including an unterminated final line.
+    pub lines: u64,
+}
+
+impl LineChunks<io::Empty> {
+    /// Start building a `LineChunks` with chained settings, which are
+    /// validated when it's built. The reader is given at the end, to
+    /// [`LineChunksBuilder::build`].
+    pub fn builder() -> LineChunksBuilder {
+        LineChunksBuilder::new()
+    }
+}
+
+/// A chunk with its metadata, from [`LineChunks::with_metadata`].
+///
+/// Apart from the last chunk of an input, `data` is non-empty and ends with a
+/// `\n`. The methods split it into lines on `\n`, so they don't apply to
+/// chunks split on another delimiter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// Index of the chunk, starting at 0.
+    pub index: u64,
+    /// Byte offset of the start of the chunk in the input.
+    pub offset: u64,
+    /// Number of lines in the chunk, including an unterminated final line.
+    pub lines: u64,
+    /// The chunk itself.
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    /// The lines of the chunk, without their `\n`. Empty lines are skipped,
+    /// as with [`LineSplitParse`].
+    pub fn lines(&self) -> impl Iterator<Item = &[u8]> {
+        self.data
+            .split(|&c| c == b'\n')
+            .filter(|line| !line.is_empty())
+    }
+
+    /// Like [`lines`](Self::lines), but with the byte offset of the start of
+    /// each line in the input.
+    pub fn lines_with_offsets(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        let mut offset = self.offset;
+        self.data
+            .split_inclusive(|&c| c == b'\n')
+            .filter_map(move |line| {
+                let start = offset;
+                offset += line.len() as u64;
+                let line = line.strip_suffix(b"\n").unwrap_or(line);
+                (!line.is_empty()).then_some((start, line))
+            })
+    }
+
+    /// The chunk as a `&str`, if it's valid UTF-8.
+    pub fn as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        utf8::as_str(&self.data)
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
 }
 
 impl<R: Read> LineChunks<R> {
@@ -30,36 +319,538 @@ impl<R: Read> LineChunks<R> {
     ///   (75% chunksize)
     /// - The default value for the upper bound of a chunk grown to include an
     ///   entire line (32 * chunksize)
+    ///
+    /// For data which is already in memory, [`SliceChunks`] yields chunks as
+    /// slices of it, rather than copying it through a `Cursor`.
     pub fn new(chunksize: usize, read: R) -> LineChunks<R> {
+        LineChunks::with_delimiter(chunksize, read, Byte)
+    }
+
+    /// Like [`new`](Self::new), but with `prefix` read before anything from
+    /// `read`. This is for bytes which have already been read from the stream,
+    /// such as the start of a line after a header. Chunks are still aligned
+    /// to lines across the join, and offsets count `prefix` as part of the
+    /// input.
+    pub fn with_initial(chunksize: usize, prefix: Vec<u8>, read: R) -> LineChunks<R> {
+        let mut chunks = LineChunks::new(chunksize, read);
+        if !prefix.is_empty() {
+            chunks.offset = prefix.len() as u64;
+            chunks.last_newline = prefix.ends_with(b"\n");
+            chunks.accum = prefix;
+        }
+        chunks
+    }
+
+    /// Like [`new`](Self::new), but read directly from the buffer of a
+    /// [`BufRead`], such as a `BufReader` or a locked stdin, rather than
+    /// stacking another buffer on top of it and copying twice. How much is
+    /// read at a time depends on the reader's buffer, and `chunksize` sets
+    /// the default chunk sizes as it does for `new`.
+    pub fn from_buf_read(chunksize: usize, read: R) -> LineChunks<R>
+    where
+        R: BufRead,
+    {
+        LineChunks::with_source(Source::direct(chunksize, read), Byte)
+    }
+
+    /// Like [`new`](Self::new), but back the IO buffer and each chunk with
+    /// transparent hugepages, to reduce TLB pressure when scanning with very
+    /// large chunk sizes. This is a hint to the kernel, which only takes effect
+    /// on Linux for buffers spanning at least one whole hugepage (usually 2MB).
+    #[cfg(feature = "hugepages")]
+    pub fn with_hugepages(chunksize: usize, read: R) -> LineChunks<R> {
+        let mut chunks = LineChunks::with_source(Source::hugepages(chunksize, read), Byte);
+        chunks.hugepages = true;
+        chunks.accum = hugepage::alloc(chunksize, true);
+        chunks
+    }
+}
+
+impl LineChunks<File> {
+    /// Like [`new`](Self::new), but for a file whose length is known from its
+    /// metadata, so [`size_hint`](Iterator::size_hint) is useful. The length
+    /// is taken from the file's current position.
+    pub fn from_file(chunksize: usize, mut file: File) -> io::Result<Self> {
+        let meta = file.metadata()?;
+        let start = if meta.is_file() {
+            Some(file.stream_position()?)
+        } else {
+            None
+        };
+
+        let mut chunks = LineChunks::new(chunksize, file);
+        if let Some(start) = start {
+            chunks.len_hint(meta.len().saturating_sub(start));
+        }
+        Ok(chunks)
+    }
+
+    /// Open a file and chunk it, with a chunk size picked from its size and
+    /// its filesystem's block size. To choose the chunk size, open the file
+    /// and use [`from_file`](Self::from_file) instead.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let meta = file.metadata()?;
+
+        #[cfg(unix)]
+        let block = std::os::unix::fs::MetadataExt::blksize(&meta);
+        #[cfg(not(unix))]
+        let block = 4096;
+
+        LineChunks::from_file(auto_chunksize(meta.len(), block), file)
+    }
+
+    /// Hint that the file will be read sequentially, so the kernel reads
+    /// further ahead of each chunk. With a cold cache and large chunks this
+    /// keeps fast storage busy, rather than waiting for each read. This only
+    /// takes effect on Linux with the `readahead` feature, using
+    /// `posix_fadvise`.
+    pub fn sequential(&mut self) {
+        readahead::advise_sequential(self.get_ref());
+    }
+}
+
+impl LineChunks<io::StdinLock<'static>> {
+    /// Chunk stdin, holding its lock for as long as the chunker lives. Stdin
+    /// is already buffered, so this reads straight from its buffer as
+    /// [`from_buf_read`](LineChunks::from_buf_read) does.
+    ///
+    /// ```no_run
+    /// for chunk in linechunks::LineChunks::stdin(1 << 20) {
+    ///     let chunk = chunk?;
+    ///     // ...
+    /// }
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn stdin(chunksize: usize) -> Self {
+        LineChunks::from_buf_read(chunksize, io::stdin().lock())
+    }
+}
+
+/// Pick a chunk size for a file of `len` bytes: about 1/64th of the file, so
+/// there are enough chunks to spread over threads, within 64KiB to 4MiB and
+/// rounded up to a whole number of filesystem blocks.
+fn auto_chunksize(len: u64, block: u64) -> usize {
+    const MIN: u64 = 64 << 10;
+    const MAX: u64 = 4 << 20;
+
+    let block = block.clamp(512, MAX);
+    (len / 64).clamp(MIN, MAX).next_multiple_of(block) as usize
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Like [`new`](Self::new), but with lines ending in `delim` rather than
+    /// `\n`. Everything described in terms of `\n` applies to the delimiter
+    /// instead. `delim` is either a [`Byte`], fixed at compile time, or a
+    /// `u8` chosen at runtime, such as `b'\0'` for `find -print0` output.
+    pub fn with_delimiter(chunksize: usize, read: R, delim: D) -> Self {
+        LineChunks::with_source(Source::buffered(chunksize, read), delim)
+    }
+
+    fn with_source(buffer: Source<R>, delim: D) -> Self {
+        let chunksize = buffer.chunksize();
         LineChunks {
-            buffer: BufReader::with_capacity(chunksize, read),
+            buffer,
+            delim,
             finished: false,
             accum: Vec::with_capacity(chunksize),
             max_line: chunksize * 32,
             min_chunk: chunksize * 3 / 4,
+            avg_line: None,
+            adaptive_size: None,
+            errors: ErrorTracker::new(ErrorPolicy::FailFast),
+            oversize: OversizePolicy::Error,
+            oversized: 0,
+            split_utf8: false,
+            skip_line: false,
+            read_failed: false,
+            offset: 0,
+            resync: false,
+            resync_from: None,
+            skipped: Vec::new(),
+            last_newline: true,
+            ends_with_newline: None,
+            hugepages: false,
+            recoverable: false,
+            // Windows reports the write end of a pipe being closed as
+            // ERROR_BROKEN_PIPE rather than a 0-length read.
+            eof_errors: if cfg!(windows) {
+                vec![ErrorKind::BrokenPipe]
+            } else {
+                Vec::new()
+            },
+            len_hint: None,
+            index: 0,
+            observer: None,
+            retry: None,
+            retries: 0,
+            follow: false,
+            poll: Duration::from_secs(1),
+            notify: None,
+            spare: None,
+            pool: None,
+            lent: 0,
         }
     }
 
     /// Max line length. That is, maximum distance we expect to see between `\n`
     /// characters. This bounds the size of the internal accumulator
-    /// buffer.
+    /// buffer. It's at least the chunk size, since a single read can be that
+    /// long, so smaller values are raised to it. A longer line is an
+    /// [`InvalidData`](ErrorKind::InvalidData) error carrying a
+    /// [`LineChunksError::MaxLineExceeded`].
     pub fn max_line(&mut self, size: usize) {
-        self.max_line = size;
+        self.max_line = size.max(self.buffer.chunksize());
     }
 
     /// Minimum acceptible chunk size. If a chunk is smaller than this then we
     /// get more input rather than returning it. The last chunk is allowed to be
-    /// shorter of course.
+    /// shorter of course. A minimum larger than `max_line` is treated as
+    /// `max_line`.
     pub fn min_chunk(&mut self, size: usize) {
         self.min_chunk = size;
     }
+
+    /// Set the minimum chunk size as a fraction of the chunk size, such as
+    /// 0.9 for 90%. Ratios outside `0.0..=1.0` are clamped to it.
+    pub fn min_chunk_ratio(&mut self, ratio: f64) {
+        let ratio = if ratio.is_nan() {
+            0.0
+        } else {
+            ratio.clamp(0.0, 1.0)
+        };
+        self.min_chunk = (self.buffer.chunksize() as f64 * ratio) as usize;
+    }
+
+    /// Adapt the minimum chunk size to the lines actually seen. When lines
+    /// turn out to be much longer than the chunk size, a fixed `min_chunk`
+    /// means almost every chunk holds a single line. In adaptive mode, a
+    /// moving average of line length is kept, and chunks are grown to hold
+    /// several lines each, up to half of `max_line`. The buffer each chunk is
+    /// accumulated in is preallocated to match, so long chunks aren't
+    /// repeatedly reallocated. `min_chunk` remains the lower bound.
+    pub fn adaptive_min_chunk(&mut self, adaptive: bool) {
+        self.avg_line = adaptive.then_some(0);
+    }
+
+    /// Adapt the chunk size to the lines actually seen, aiming for about a
+    /// thousand lines per chunk, within `min..=max` bytes. Chunks of short
+    /// lines are then not too small, and chunks of long lines not too large,
+    /// without knowing the input in advance. Chunks are at least 75% of the
+    /// current target, and no larger than it unless a line doesn't fit. The
+    /// target is also kept below half of `max_line`. This overrides
+    /// `min_chunk`, and the chunk size given to the constructor is just the
+    /// initial target and IO buffer size.
+    pub fn adaptive_chunk_size(&mut self, min: usize, max: usize) {
+        self.adaptive_size = Some((min.max(1), max.max(min).max(1)));
+        self.avg_line.get_or_insert(0);
+    }
+
+    fn adaptive_target(&self) -> Option<usize> {
+        let (min, max) = self.adaptive_size?;
+        let target = match self.avg_line {
+            Some(avg) if avg > 0 => avg.saturating_mul(ADAPTIVE_SIZE_LINES),
+            _ => self.buffer.chunksize(),
+        };
+        Some(target.clamp(min, max).min(self.max_line / 2).max(1))
+    }
+
+    fn effective_min_chunk(&self) -> usize {
+        if let Some(target) = self.adaptive_target() {
+            return (target / 4 * 3).min(self.max_line);
+        }
+        match self.avg_line {
+            Some(avg) => {
+                let target = avg.saturating_mul(ADAPTIVE_LINES);
+                self.min_chunk.max(target.min(self.max_line / 2))
+            }
+            None => self.min_chunk,
+        }
+        .min(self.max_line)
+    }
+
+    /// How to handle errors. With the default [`ErrorPolicy::FailFast`] the
+    /// first error ends iteration. Otherwise tolerated errors are recorded in
+    /// the [`error_report`](Self::error_report) and chunking continues: a line
+    /// exceeding `max_line` is dropped, and a failed read is retried. A read
+    /// which fails again without any progress being made in between is
+    /// always returned, so a broken reader can't spin forever.
+    pub fn error_policy(&mut self, policy: ErrorPolicy) {
+        self.errors.policy = policy;
+    }
+
+    /// What to do with a line longer than `max_line`. With the default
+    /// [`OversizePolicy::Error`] it's an error, handled by the
+    /// [`ErrorPolicy`]. The other policies keep going without an error, and
+    /// count the line in [`oversized_lines`](Self::oversized_lines).
+    pub fn oversize_policy(&mut self, policy: OversizePolicy) {
+        self.oversize = policy;
+    }
+
+    /// When an over-long line is split with [`OversizePolicy::SplitMidLine`]
+    /// or [`OversizePolicy::Truncate`], back off to the start of any UTF-8
+    /// character which would be cut in two, so every piece of a UTF-8 line
+    /// is valid UTF-8 by itself.
+    pub fn split_on_char_boundary(&mut self, utf8: bool) {
+        self.split_utf8 = utf8;
+    }
+
+    /// Number of over-long lines split, skipped or truncated by the
+    /// [`OversizePolicy`]. A line split several times is counted once per
+    /// split.
+    pub fn oversized_lines(&self) -> u64 {
+        self.oversized
+    }
+
+    /// Treat read errors of `kind` as the end of the input, returning any
+    /// remaining data as the last chunk. This is how line-oriented tools
+    /// usually behave when their upstream goes away, with errors like
+    /// [`ErrorKind::BrokenPipe`] or [`ErrorKind::ConnectionReset`].
+    pub fn eof_on(&mut self, kind: ErrorKind) {
+        if !self.eof_errors.contains(&kind) {
+            self.eof_errors.push(kind);
+        }
+    }
+
+    /// The expected length of the input, so [`size_hint`](Iterator::size_hint)
+    /// can bound the number of chunks. This is only a hint: if the input
+    /// turns out to be longer, so might the iteration.
+    pub fn len_hint(&mut self, len: u64) {
+        self.len_hint = Some(len);
+    }
+
+    /// Carry on after returning an error, rather than ending iteration, so
+    /// the caller can decide whether to keep pulling chunks. After a read
+    /// error the next call reads again, continuing from the data
+    /// accumulated so far. After a line exceeds `max_line`, the rest of it is
+    /// skipped. A reader which keeps failing keeps returning errors, so the
+    /// caller must stop at some point.
+    pub fn recoverable(&mut self, recoverable: bool) {
+        self.recoverable = recoverable;
+    }
+
+    /// Decide whether to retry reads which fail with transient errors. `retry`
+    /// is called with the error and the number of times the read has already
+    /// been retried, and returns true to try again, perhaps after sleeping
+    /// to back off. Retried errors aren't seen by the [`ErrorPolicy`].
+    /// Reads interrupted by a signal ([`ErrorKind::Interrupted`]) are always
+    /// retried, as with [`Read::read_to_end`].
+    pub fn retry_if<F>(&mut self, retry: F)
+    where
+        F: FnMut(&io::Error, u32) -> bool + Send + 'static,
+    {
+        self.retry = Some(Box::new(retry));
+    }
+
+    /// Follow the input as it grows, like `tail -f`. Reaching the end of the
+    /// input doesn't end iteration: any complete lines are returned, even if
+    /// they're shorter than `min_chunk`, and the chunker then waits and reads
+    /// again. A partial line at the end is held back until it's completed.
+    /// By default it polls once a second, which
+    /// [`poll_interval`](Self::poll_interval) changes. Since the input never
+    /// ends, [`ends_with_newline`](Self::ends_with_newline) stays `None`.
+    pub fn follow(&mut self, follow: bool) {
+        self.follow = follow;
+    }
+
+    /// How long to sleep at the end of the input before reading again, when
+    /// following it.
+    pub fn poll_interval(&mut self, interval: Duration) {
+        self.poll = interval;
+    }
+
+    /// When following the input, call `wait` at the end of it rather than
+    /// polling. It should block until the input may have grown, for example
+    /// on a file watcher or a channel from the writer. An error from it is
+    /// returned as a chunk error.
+    pub fn follow_notify<F>(&mut self, wait: F)
+    where
+        F: FnMut() -> io::Result<()> + Send + 'static,
+    {
+        self.notify = Some(Box::new(wait));
+    }
+
+    /// Wait for more input when following it.
+    fn wait(&mut self) -> io::Result<()> {
+        match &mut self.notify {
+            Some(notify) => notify(),
+            None => {
+                thread::sleep(self.poll);
+                Ok(())
+            }
+        }
+    }
+
+    /// Call `observer` with information about every chunk as it's returned,
+    /// for logging, metrics or debugging. Offsets are positions in the input
+    /// as read, so they include any data skipped over.
+    pub fn on_chunk<F>(&mut self, observer: F)
+    where
+        F: FnMut(&ChunkInfo) + Send + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Resynchronise after a tolerated read error, rather than assuming the
+    /// reader carries on where it left off. The partial line before the error
+    /// is dropped, as is everything up to the first `\n` after the next
+    /// successful read, so chunking resumes at a line boundary. The dropped
+    /// byte ranges are recorded in [`skipped_ranges`](Self::skipped_ranges).
+    ///
+    /// This is for readers which skip past a bad region when a read fails,
+    /// such as files on a flaky network filesystem. Use it with
+    /// [`ErrorPolicy::CollectUpTo`] to tolerate up to `n` bad regions.
+    pub fn resync_on_read_error(&mut self, resync: bool) {
+        self.resync = resync;
+    }
+
+    /// Byte ranges dropped while resynchronising after read errors. The
+    /// offsets are positions in the stream as read, so they don't include
+    /// anything the reader itself skipped over.
+    pub fn skipped_ranges(&self) -> &[Range<u64>] {
+        &self.skipped
+    }
+
+    /// Errors tolerated by the [`ErrorPolicy`] so far.
+    pub fn error_report(&self) -> &ErrorReport<io::Error> {
+        &self.errors.report
+    }
+
+    /// Consume the chunker, returning the reader and any input which has been
+    /// read from it but not returned in a chunk. After an error this is the
+    /// data which was lost, so it can be logged, or iteration retried from
+    /// that point. Complete lines accumulated before an over-long line are
+    /// included, but the over-long line itself is in the error.
+    pub fn into_parts(self) -> (R, Vec<u8>) {
+        let mut pending = self.accum;
+        pending.drain(..self.lent);
+        pending.extend_from_slice(self.buffer.buffer());
+        (self.buffer.into_inner(), pending)
+    }
+
+    /// Stop chunking and return a reader which continues from where the last
+    /// chunk ended: it yields the input which has been read but not returned
+    /// in a chunk, then the rest of the underlying reader. This is for
+    /// handing over to other code partway through, for example to read a
+    /// binary trailer.
+    pub fn into_inner(self) -> io::Chain<io::Cursor<Vec<u8>>, R> {
+        let (read, pending) = self.into_parts();
+        io::Cursor::new(pending).chain(read)
+    }
+
+    /// The underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.buffer.get_ref()
+    }
+
+    /// The underlying reader. Reading from it directly skips over any input
+    /// which has been read but not yet returned in a chunk.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.buffer.get_mut()
+    }
+
+    /// Whether the input ended with a `\n`. This is `None` until the end of
+    /// the input has been reached, and then `Some(false)` if the last line was
+    /// unterminated. An empty input counts as ending with a newline.
+    pub fn ends_with_newline(&self) -> Option<bool> {
+        self.ends_with_newline
+    }
 }
 
-impl<R: Read> Iterator for LineChunks<R> {
-    type Item = io::Result<Vec<u8>>;
+impl<R: Read + Seek, D: Delimiter> LineChunks<R, D> {
+    /// Skip forward approximately `n` chunks without reading them.
+    ///
+    /// This seeks forward `n * chunksize` bytes from the start of the next
+    /// chunk, then realigns to the following line boundary, so it's much
+    /// cheaper than `nth` for seekable sources. The next chunk starts at the
+    /// first line which begins at or after the seek target. It's an
+    /// [`InvalidInput`](ErrorKind::InvalidInput) error if the distance
+    /// overflows a seek offset.
+    pub fn skip_chunks(&mut self, n: usize) -> io::Result<()> {
+        if n == 0 || self.finished {
+            return Ok(());
+        }
+        self.accum.drain(..mem::take(&mut self.lent));
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let chunksize = self.buffer.capacity();
+        // The next chunk would start at the beginning of `accum`. Seek to
+        // the byte before the target so that if the target is already at the
+        // start of a line, we don't skip it when realigning.
+        let target = n
+            .checked_mul(self.buffer.chunksize())
+            .and_then(|len| i64::try_from(len).ok())
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "skip distance overflows"))?
+            - self.accum.len() as i64;
+        self.buffer.seek_relative(target - 1)?;
+        self.offset = self.offset.wrapping_add_signed(target - 1);
+        self.accum.clear();
+        self.skip_line = true;
+
+        Ok(())
+    }
+
+    /// Seek so that the next chunk starts at `offset` in the stream, which
+    /// must be the start of a line.
+    pub(crate) fn seek_to(&mut self, offset: u64) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.accum.drain(..mem::take(&mut self.lent));
+
+        let target = i64::try_from(i128::from(offset) - i128::from(self.offset))
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "seek distance overflows"))?;
+        self.buffer.seek_relative(target)?;
+        self.offset = offset;
+        self.accum.clear();
+        self.skip_line = false;
+
+        Ok(())
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Read the next chunk, along with the stream offset of its end.
+    fn read_chunk(&mut self) -> Option<io::Result<(Vec<u8>, u64)>> {
+        let (len, end) = match self.fill_chunk()? {
+            Ok(chunk) => chunk,
+            Err(err) => return Some(Err(err)),
+        };
+
+        // The buffer we put in place here is going to be used for the next
+        // chunk so we may as well give it enough capacity to handle it.
+        let chunksize = self.buffer_size();
+        let mut new = match (self.spare.take(), &self.pool) {
+            (Some(mut spare), _) => {
+                spare.reserve(chunksize);
+                spare
+            }
+            (None, Some(pool)) => pool.take(chunksize),
+            (None, None) => hugepage::alloc(chunksize, self.hugepages),
+        };
+        new.extend_from_slice(&self.accum[len..]);
+        self.accum.truncate(len);
+        Some(Ok((mem::replace(&mut self.accum, new), end)))
+    }
+
+    /// Size of the buffer to accumulate the next chunk in.
+    fn buffer_size(&self) -> usize {
+        match (self.adaptive_target(), self.avg_line) {
+            (Some(target), _) => target,
+            (None, Some(avg)) => self
+                .buffer
+                .chunksize()
+                .max(self.effective_min_chunk().saturating_add(avg)),
+            (None, None) => self.buffer.chunksize(),
+        }
+    }
+
+    /// Accumulate the next chunk at the start of `accum`, returning its
+    /// length and the stream offset of its end. Anything after it in
+    /// `accum` belongs to the following chunk.
+    fn fill_chunk(&mut self) -> Option<io::Result<(usize, u64)>> {
+        self.accum.drain(..mem::take(&mut self.lent));
+        let delim = self.delim.byte();
+        let min_chunk = self.effective_min_chunk();
+        let target = self.adaptive_target();
 
         loop {
             if self.finished {
@@ -69,130 +860,569 @@ impl<R: Read> Iterator for LineChunks<R> {
             // Check to see if we've accumulated too much and we've given up
             // finding another line break.
             if self.accum.len() > self.max_line {
-                self.finished = true;
+                let keep = memchr::memrchr(delim, &self.accum).map_or(0, |eol| eol + 1);
+                let end = self.offset - (self.accum.len() - keep) as u64;
+                match self.oversize {
+                    OversizePolicy::Error => {
+                        let partial = self.accum.split_off(keep);
+                        let err = LineChunksError::MaxLineExceeded {
+                            offset: end,
+                            accumulated: partial.len(),
+                            partial,
+                        };
 
-                break Some(Err(io::Error::new(
-                    ErrorKind::OutOfMemory,
-                    format!("Max line length exceeded: {}", self.accum.len()),
-                )));
+                        if let Some(err) = self.errors.tolerate(err.into()) {
+                            // Skip the rest of the line if we carry on.
+                            self.finished = !self.recoverable;
+                            self.skip_line = true;
+                            break Some(Err(err));
+                        }
+                    }
+                    OversizePolicy::SkipLine => self.oversized += 1,
+                    OversizePolicy::SplitMidLine => {
+                        self.oversized += 1;
+                        let mut split = self.accum.len();
+                        if self.split_utf8 {
+                            split = keep + utf8::char_boundary(&self.accum[keep..]);
+                        }
+
+                        let end = self.offset - (self.accum.len() - split) as u64;
+                        break Some(Ok((split, end)));
+                    }
+                    OversizePolicy::Truncate => {
+                        self.oversized += 1;
+                        let start = self.offset - self.accum.len() as u64;
+                        let mut len = self.max_line.min(self.accum.len() - keep);
+                        if self.split_utf8 {
+                            len = utf8::char_boundary(&self.accum[keep..keep + len]);
+                        }
+
+                        // Report the end relative to the start, so the
+                        // chunk's start offset is still right.
+                        self.accum.truncate(keep + len);
+                        self.accum.push(delim);
+                        self.skip_line = true;
+                        let end = start + self.accum.len() as u64;
+                        break Some(Ok((self.accum.len(), end)));
+                    }
+                }
+
+                // Drop the partial line and skip the rest of it, but keep
+                // any complete lines accumulated before it.
+                self.accum.truncate(keep);
+                self.skip_line = true;
+
+                if !self.accum.is_empty() {
+                    break Some(Ok((self.accum.len(), end)));
+                }
             }
 
             let chunk = match self.buffer.fill_buf() {
-                Ok(chunk) => chunk,
+                Ok(chunk) => {
+                    self.read_failed = false;
+                    self.retries = 0;
+                    chunk
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) if self.eof_errors.contains(&err.kind()) => &[],
+                Err(err)
+                    if self
+                        .retry
+                        .as_mut()
+                        .is_some_and(|retry| retry(&err, self.retries)) =>
+                {
+                    self.retries += 1;
+                    continue;
+                }
                 Err(err) => {
-                    // Return an IO error (once). `accum` data is dropped.
-                    self.finished = true;
-                    break Some(Err(err));
+                    // Return an IO error (once) unless the policy lets us
+                    // retry. `accum` data is dropped if we stop, and kept
+                    // for the next read if we're recoverable.
+                    let err = if mem::replace(&mut self.read_failed, true) {
+                        Some(err)
+                    } else {
+                        self.errors.tolerate(err)
+                    };
+                    match err {
+                        Some(err) => {
+                            self.finished = !self.recoverable;
+                            break Some(Err(err));
+                        }
+                        None if self.resync => {
+                            // Drop the partial line, then skip to the next
+                            // line boundary. Complete lines before it are
+                            // returned now, so chunks are contiguous.
+                            let keep = memchr::memrchr(delim, &self.accum).map_or(0, |eol| eol + 1);
+                            let end = self.offset - (self.accum.len() - keep) as u64;
+                            self.accum.truncate(keep);
+                            self.resync_from.get_or_insert(end);
+                            self.skip_line = true;
+
+                            if !self.accum.is_empty() {
+                                break Some(Ok((self.accum.len(), end)));
+                            }
+                            continue;
+                        }
+                        None => continue,
+                    }
                 }
             };
 
+            if chunk.is_empty() && self.follow {
+                // Return any complete lines, then wait for more.
+                let keep = memchr::memrchr(delim, &self.accum).map_or(0, |eol| eol + 1);
+                if keep > 0 {
+                    let end = self.offset - (self.accum.len() - keep) as u64;
+                    break Some(Ok((keep, end)));
+                }
+                if let Err(err) = self.wait() {
+                    self.finished = !self.recoverable;
+                    break Some(Err(err));
+                }
+                continue;
+            }
+
             if chunk.is_empty() {
                 // Handle EOF. Return `accum` before finishing the iterator.
                 self.finished = true;
+                self.ends_with_newline = Some(self.last_newline);
+                if let Some(start) = self.resync_from.take() {
+                    self.skipped.push(start..self.offset);
+                }
 
-                let accum = mem::take(&mut self.accum);
+                break (!self.accum.is_empty()).then_some(Ok((self.accum.len(), self.offset)));
+            }
 
-                break if accum.is_empty() {
-                    None
-                } else {
-                    Some(Ok(accum))
+            if self.skip_line {
+                // Discard up to and including the next \n
+                let len = match memchr::memchr(delim, chunk) {
+                    Some(eol) => {
+                        self.skip_line = false;
+                        eol + 1
+                    }
+                    None => chunk.len(),
                 };
+                self.last_newline = chunk[len - 1] == delim;
+                self.buffer.consume(len);
+                self.offset += len as u64;
+                if !self.skip_line {
+                    if let Some(start) = self.resync_from.take() {
+                        self.skipped.push(start..self.offset);
+                    }
+                }
+                continue;
             }
 
+            // Don't look past an adaptive target size, so chunks can be
+            // smaller than the buffer. The rest stays buffered.
+            let chunk = match target {
+                Some(target) if self.accum.len() < target => {
+                    &chunk[..chunk.len().min(target - self.accum.len())]
+                }
+                _ => chunk,
+            };
+
             // Find chunk's last line boundary
-            match memchr::memrchr(b'\n', chunk) {
+            match memchr::memrchr(delim, chunk) {
                 Some(eol) => {
-                    // Grab the chunk up to the last \n, prepend any prior
-                    // accumulated buffer and return that as our item
+                    // Grab the chunk up to the last \n, append it to any
+                    // prior accumulated data and return that as our item
                     let eol = eol + 1; // include \n
+                    self.accum.extend_from_slice(&chunk[..eol]);
 
-                    // The buffer we put in place here is going to be used for
-                    // the next chunk so we may as well give it enough capacity
-                    // to handle it.
-                    let mut buf = mem::replace(&mut self.accum, Vec::with_capacity(chunksize));
-                    buf.extend_from_slice(&chunk[..eol]);
-                    debug_assert!(!buf.is_empty());
-
+                    self.last_newline = true;
                     self.buffer.consume(eol);
+                    self.offset += eol as u64;
 
                     // Only return the chunk if it's large enough
-                    if buf.len() >= self.min_chunk {
-                        break Some(Ok(buf));
+                    if self.accum.len() >= min_chunk {
+                        break Some(Ok((self.accum.len(), self.offset)));
                     }
-
-                    // If it's a short chunk put it back into accum
-                    debug_assert!(self.accum.is_empty());
-                    self.accum = buf;
                 }
                 None => {
                     // If we didn't find a \n in the chunk, make a copy of the
                     // whole thing to prepend onto the next one.
                     let len = chunk.len();
                     self.accum.extend_from_slice(chunk);
+                    self.last_newline = false;
                     self.buffer.consume(len);
+                    self.offset += len as u64;
                 }
             }
         }
     }
 }
 
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Read the next chunk into `buf`, replacing its contents, and return
+    /// whether there was one. The buffer's allocation is reused for a later
+    /// chunk, so a loop which doesn't keep chunks doesn't allocate for each
+    /// one. At the end of the input `buf` is left empty.
+    ///
+    /// ```
+    /// let mut chunks = linechunks::LineChunks::new(2, &b"a\nb\n"[..]);
+    /// let mut buf = Vec::new();
+    /// let mut lines = 0;
+    /// while chunks.next_chunk_into(&mut buf)? {
+    ///     lines += buf.iter().filter(|&&c| c == b'\n').count();
+    /// }
+    /// assert_eq!(lines, 2);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn next_chunk_into(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        if self.spare.is_none() && buf.capacity() > 0 {
+            buf.clear();
+            self.spare = Some(mem::take(buf));
+        }
+
+        match self.next_chunk(false) {
+            Some(chunk) => {
+                *buf = chunk?.data;
+                Ok(true)
+            }
+            None => {
+                buf.clear();
+                Ok(false)
+            }
+        }
+    }
+
+    /// Return the next chunk as a view into the chunker's own buffer, rather
+    /// than as a new `Vec`. The chunk is valid until the next call. Chunks
+    /// are accumulated in the same buffer each time, so no buffer is
+    /// allocated per chunk. Otherwise this is the same as
+    /// [`next`](Iterator::next): all the same settings apply, and an
+    /// [`on_chunk`](Self::on_chunk) observer sees these chunks too.
+    ///
+    /// ```
+    /// let mut chunks = linechunks::LineChunks::new(4, &b"a\nb\nc\n"[..]);
+    /// let mut lines = 0;
+    /// while let Some(chunk) = chunks.next_borrowed()? {
+    ///     lines += chunk.iter().filter(|&&c| c == b'\n').count();
+    /// }
+    /// assert_eq!(lines, 3);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn next_borrowed(&mut self) -> io::Result<Option<&[u8]>> {
+        let (len, end) = match self.fill_chunk() {
+            Some(chunk) => chunk?,
+            None => return Ok(None),
+        };
+
+        let accum = mem::take(&mut self.accum);
+        self.record(&accum[..len], end, false);
+        self.accum = accum;
+        self.lent = len;
+        Ok(Some(&self.accum[..len]))
+    }
+
+    /// Return the next chunk with its metadata. Lines are only counted if
+    /// `count_lines` is set (or they're needed anyway), and are 0 otherwise.
+    pub(crate) fn next_chunk(&mut self, count_lines: bool) -> Option<io::Result<Chunk>> {
+        let (chunk, end) = match self.read_chunk()? {
+            Ok(chunk) => chunk,
+            Err(err) => return Some(Err(err)),
+        };
+        let (start, lines) = self.record(&chunk, end, count_lines);
+
+        Some(Ok(Chunk {
+            index: self.index - 1,
+            offset: start,
+            lines,
+            data: chunk,
+        }))
+    }
+
+    /// Count the chunk, and its lines if they're needed, returning its start
+    /// offset and its line count, or 0 if they weren't counted.
+    fn record(&mut self, chunk: &[u8], end: u64, count_lines: bool) -> (u64, u64) {
+        let start = end - chunk.len() as u64;
+        let mut lines = 0;
+
+        if count_lines || self.observer.is_some() || self.avg_line.is_some() {
+            let delim = self.delim.byte();
+            lines = memchr::memchr_iter(delim, chunk).count() as u64
+                + u64::from(!chunk.ends_with(&[delim]));
+
+            if let Some(avg) = &mut self.avg_line {
+                // Exponential moving average, weighting this chunk by 1/8.
+                let len = chunk.len() / lines as usize;
+                *avg = if *avg == 0 {
+                    len
+                } else {
+                    *avg - *avg / 8 + len / 8
+                };
+            }
+            if let Some(observer) = &mut self.observer {
+                observer(&ChunkInfo {
+                    index: self.index,
+                    offset: start,
+                    len: chunk.len(),
+                    lines,
+                });
+            }
+        }
+        self.index += 1;
+
+        (start, lines)
+    }
+}
+
+impl<R: Read, D: Delimiter> Iterator for LineChunks<R, D> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_chunk(false)?.map(|chunk| chunk.data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            return (0, Some(0));
+        }
+        let Some(len) = self.len_hint else {
+            return (0, None);
+        };
+
+        // Every chunk but the last is at least `min_chunk` long, and there
+        // may be an error at the end. There's no useful lower bound, since
+        // the rest of the input might be dropped as an over-long line.
+        let remaining = len.saturating_sub(self.offset) + self.accum.len() as u64;
+        let min_chunk = self.effective_min_chunk().max(1) as u64;
+        let upper = (!self.recoverable)
+            .then(|| usize::try_from(remaining.div_ceil(min_chunk) + 1).ok())
+            .flatten();
+
+        (0, upper)
+    }
+}
+
 /// Split a chunk into individual lines and apply a parser function to each.
 /// Parser can return Some(result) or None if the item should be skipped.
-pub struct LineSplitParse<F> {
-    buf: Vec<u8>,
-    lim: usize,
+/// Use [`LineSplitPos`] if the parser needs to know where each line is, or
+/// [`TryLineSplitParse`] for a fallible parser whose errors should say where
+/// they happened.
+///
+/// Lines can also be taken from the end of the chunk with `.rev()`. The
+/// chunk is usually a `Vec<u8>`, but can be any buffer of bytes.
+pub struct LineSplitParse<F, B = Vec<u8>> {
+    buf: B,
+    split: Splitter,
     parser: F,
 }
 
-impl<F> LineSplitParse<F> {
-    pub fn new(buf: Vec<u8>, parser: F) -> Self {
+impl<F, B: AsRef<[u8]>> LineSplitParse<F, B> {
+    pub fn new(buf: B, parser: F) -> Self {
         Self {
+            split: Splitter::new(buf.as_ref().len()),
             buf,
             parser,
-            lim: 0,
         }
     }
+
+    /// Strip the `\r` from lines ending in `\r\n`, as written on Windows.
+    /// This doesn't apply to lines passed with their terminator.
+    pub fn trim_crlf(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Crlf } else { Trim::None };
+    }
+
+    /// Strip ASCII whitespace, including any `\r`, from both ends of each
+    /// line. Lines which are only whitespace are then empty. This doesn't
+    /// apply to lines passed with their terminator.
+    pub fn trim_whitespace(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Whitespace } else { Trim::None };
+    }
+
+    /// Pass lines to the parser with their terminating `\n`, if they have
+    /// one.
+    pub fn keep_terminator(&mut self, keep: bool) {
+        self.split.keep_terminator = keep;
+    }
+
+    /// Pass empty lines to the parser, rather than skipping them. With both
+    /// this and [`keep_terminator`](Self::keep_terminator), the lines passed
+    /// to the parser concatenate back to the original chunk.
+    pub fn keep_empty(&mut self, keep: bool) {
+        self.split.keep_empty = keep;
+    }
+
+    /// Split lines on `delim` rather than `\n`, to match chunks from a
+    /// [`LineChunks`] with another delimiter.
+    pub fn delimiter(&mut self, delim: u8) {
+        self.split.delim = delim;
+    }
+
+    /// Apply an [`ErrorPolicy`] to the results of a fallible parser.
+    pub fn error_policy<T, E>(self, policy: ErrorPolicy) -> WithPolicy<Self, E>
+    where
+        F: FnMut(&[u8]) -> Result<T, E>,
+    {
+        WithPolicy::new(self, policy)
+    }
 }
 
-impl<F, T> Iterator for LineSplitParse<F>
+impl<F, T, B: AsRef<[u8]>> Iterator for LineSplitParse<F, B>
 where
     F: FnMut(&[u8]) -> T,
 {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if self.lim == self.buf.len() {
-                break None;
-            }
-
-            debug_assert!(!self.buf.is_empty());
-
-            let (eol, new_lim) = memchr::memchr(b'\n', &self.buf[self.lim..])
-                .map(|eol| (self.lim + eol, self.lim + eol + 1))
-                .unwrap_or((self.buf.len(), self.buf.len()));
-            let lim = mem::replace(&mut self.lim, new_lim);
-            let slice = &self.buf[lim..eol];
-
-            if slice.is_empty() {
-                continue;
-            }
+        let buf = self.buf.as_ref();
+        let (_, range) = self.split.next_line(buf)?;
+        Some((self.parser)(&buf[range]))
+    }
+}
 
-            break Some((self.parser)(slice));
-        }
+impl<F, T, B: AsRef<[u8]>> DoubleEndedIterator for LineSplitParse<F, B>
+where
+    F: FnMut(&[u8]) -> T,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let buf = self.buf.as_ref();
+        let (_, range) = self.split.next_back_line(buf)?;
+        Some((self.parser)(&buf[range]))
     }
 }
 
 #[cfg(test)]
 mod test {
     use rayon::prelude::*;
-    use std::{
-        fs::File,
-        sync::atomic::{AtomicUsize, Ordering},
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
     };
 
     use super::*;
 
+    #[test]
+    fn test_split_rev() {
+        let chunk = b"a\n\nbb\r\nccc".to_vec();
+
+        let mut split = LineSplitParse::new(chunk.clone(), <[u8]>::to_vec);
+        split.trim_crlf(true);
+        let back: Vec<_> = split.rev().collect();
+        assert_eq!(back, vec![b"ccc".to_vec(), b"bb".to_vec(), b"a".to_vec()]);
+
+        // Meeting in the middle yields each line once
+        let mut split = LineSplitParse::new(chunk.clone(), <[u8]>::to_vec);
+        split.keep_empty(true);
+        split.keep_terminator(true);
+        assert_eq!(split.next_back(), Some(b"ccc".to_vec()));
+        assert_eq!(split.next(), Some(b"a\n".to_vec()));
+        assert_eq!(split.next_back(), Some(b"bb\r\n".to_vec()));
+        assert_eq!(split.next_back(), Some(b"\n".to_vec()));
+        assert_eq!(split.next(), None);
+        assert_eq!(split.next_back(), None);
+    }
+
+    #[test]
+    fn test_follow() {
+        // Returns each piece in turn, with an empty piece as EOF.
+        struct Growing(Vec<&'static [u8]>);
+        impl Read for Growing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Ok(0);
+                }
+                let piece = self.0.remove(0);
+                buf[..piece.len()].copy_from_slice(piece);
+                Ok(piece.len())
+            }
+        }
+
+        let waits = Arc::new(AtomicUsize::new(0));
+        let mut chunks = LineChunks::new(8, Growing(vec![b"a\nb", b"", b"c\n", b""]));
+        chunks.follow(true);
+        chunks.follow_notify({
+            let waits = waits.clone();
+            move || match waits.fetch_add(1, Ordering::Relaxed) {
+                0 => Ok(()),
+                _ => Err(io::Error::other("stop")),
+            }
+        });
+
+        assert_eq!(chunks.next().unwrap().unwrap(), b"a\n");
+        assert_eq!(chunks.next().unwrap().unwrap(), b"bc\n");
+        assert_eq!(chunks.next().unwrap().unwrap_err().to_string(), "stop");
+        assert!(chunks.next().is_none());
+        assert_eq!(waits.load(Ordering::Relaxed), 2);
+        assert_eq!(chunks.ends_with_newline(), None);
+    }
+
+    #[test]
+    fn test_next_chunk_into() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let mut chunks = LineChunks::new(64, input.as_bytes());
+        let mut buf = Vec::new();
+        let mut output = Vec::new();
+        let mut ptrs = Vec::new();
+
+        while chunks.next_chunk_into(&mut buf).unwrap() {
+            output.extend_from_slice(&buf);
+            if !ptrs.contains(&buf.as_ptr()) {
+                ptrs.push(buf.as_ptr());
+            }
+        }
+        assert_eq!(output, input.as_bytes());
+        assert!(buf.is_empty());
+        // Two buffers take turns, apart from any regrown ones.
+        assert!(ptrs.len() < 5);
+    }
+
+    #[test]
+    fn test_next_borrowed() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+
+        for size in [4, 64, 10_000] {
+            let mut chunks = LineChunks::new(size, input.as_bytes());
+            let mut output = Vec::new();
+            while let Some(chunk) = chunks.next_borrowed().unwrap() {
+                assert!(chunk.len() >= size * 3 / 4 || output.len() + chunk.len() == input.len());
+                output.extend_from_slice(chunk);
+            }
+            assert_eq!(output, input.as_bytes());
+            assert_eq!(chunks.ends_with_newline(), Some(true));
+        }
+
+        let mut chunks = LineChunks::new(4, &b"a\nb\nmuch too long\nc"[..]);
+        chunks.max_line(8);
+        chunks.min_chunk(1);
+        assert_eq!(chunks.next_borrowed().unwrap(), Some(&b"a\nb\n"[..]));
+        assert!(chunks.next_borrowed().is_err());
+        assert_eq!(chunks.next_borrowed().unwrap(), None);
+
+        // The same policies, skipping and observer as `next`.
+        let input = "a\nb\nmuch too long\nc\nd\n";
+        let setup = || {
+            let mut chunks = LineChunks::new(4, io::Cursor::new(input));
+            chunks.max_line(8);
+            chunks.min_chunk(1);
+            chunks.error_policy(ErrorPolicy::SkipAndCount);
+            chunks
+        };
+        let expected: Vec<Vec<u8>> = setup().map(Result::unwrap).collect();
+        let mut chunks = setup();
+        let seen = Arc::new(AtomicUsize::new(0));
+        chunks.on_chunk({
+            let seen = seen.clone();
+            move |_| {
+                seen.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let mut borrowed = Vec::new();
+        while let Some(chunk) = chunks.next_borrowed().unwrap() {
+            borrowed.push(chunk.to_vec());
+        }
+        assert_eq!(borrowed, expected);
+        assert_eq!(seen.load(Ordering::Relaxed), expected.len());
+
+        // Skipping lands on a line start.
+        let mut chunks = setup();
+        chunks.next_borrowed().unwrap();
+        chunks.skip_chunks(1).unwrap();
+        assert_eq!(chunks.next_borrowed().unwrap(), Some(&b"c\nd\n"[..]));
+    }
+
     #[test]
     fn test_max_linelen() {
         let file = File::open("/dev/zero").expect("/dev/zero open failed");
@@ -202,6 +1432,627 @@ mod test {
         assert!(chunker.next().is_none());
     }
 
+    #[test]
+    fn test_config_clamp() {
+        let input = b"aaaaaaaaaaaa\nb\n";
+
+        // max_line is raised to the chunk size, so a line of that length is
+        // fine.
+        let mut chunker = LineChunks::new(16, &input[..]);
+        chunker.max_line(4);
+        assert_eq!(chunker.max_line, 16);
+        assert!(chunker.all(|chunk| chunk.is_ok()));
+
+        let mut chunker = LineChunks::new(100, &input[..]);
+        chunker.min_chunk_ratio(0.9);
+        assert_eq!(chunker.min_chunk, 90);
+        chunker.min_chunk_ratio(2.0);
+        assert_eq!(chunker.min_chunk, 100);
+
+        chunker.min_chunk(10_000);
+        assert_eq!(chunker.effective_min_chunk(), 3200);
+    }
+
+    #[test]
+    fn test_max_linelen_skip() {
+        let input = b"short\nthis line is much too long\nshort again\n";
+        let mut chunker = LineChunks::new(4, &input[..]);
+        chunker.max_line(12);
+        chunker.error_policy(ErrorPolicy::SkipAndCount);
+
+        let data: Vec<u8> = chunker.by_ref().flat_map(Result::unwrap).collect();
+        assert_eq!(data, b"short\nshort again\n");
+        assert_eq!(chunker.error_report().skipped, 1);
+
+        // Short lines accumulated before the long one are kept
+        let input = b"a\nb\nthis line is much too long\nc\n";
+        let mut chunker = LineChunks::new(8, &input[..]);
+        chunker.max_line(10);
+        chunker.error_policy(ErrorPolicy::SkipAndCount);
+
+        let data: Vec<u8> = chunker.by_ref().flat_map(Result::unwrap).collect();
+        assert_eq!(data, b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_read_retry() {
+        struct Flaky<'a>(&'a [u8], usize);
+
+        impl Read for Flaky<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                if self.1.is_multiple_of(2) {
+                    return Err(io::Error::other("flaky"));
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let input = b"one\ntwo\nthree\n";
+        let mut chunker = LineChunks::new(4, Flaky(&input[..], 0));
+        chunker.error_policy(ErrorPolicy::CollectUpTo(10));
+
+        let data: Vec<u8> = chunker.by_ref().flat_map(Result::unwrap).collect();
+        assert_eq!(data, input);
+        assert_eq!(chunker.error_report().errors.len(), 4);
+    }
+
+    #[test]
+    fn test_read_resync() {
+        // Fails once at offset 11, then carries on 4 bytes further along.
+        struct BadRegion<'a>(&'a [u8], usize);
+
+        impl Read for BadRegion<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.1 == 11 {
+                    self.1 += 4;
+                    return Err(io::Error::other("bad region"));
+                }
+                let len = buf.len().min(self.0.len() - self.1);
+                let len = if self.1 < 11 {
+                    len.min(11 - self.1)
+                } else {
+                    len
+                };
+                buf[..len].copy_from_slice(&self.0[self.1..self.1 + len]);
+                self.1 += len;
+                Ok(len)
+            }
+        }
+
+        let input = b"one\ntwo\nthree\nfour\nfive\n";
+        let mut chunker = LineChunks::new(4, BadRegion(&input[..], 0));
+        chunker.min_chunk(1);
+        chunker.resync_on_read_error(true);
+        chunker.error_policy(ErrorPolicy::CollectUpTo(1));
+
+        let data: Vec<u8> = chunker.by_ref().flat_map(Result::unwrap).collect();
+        // "thr" had been read when the read failed and the reader skipped
+        // "ee\nf", so "our\n" is dropped to resync.
+        assert_eq!(data, b"one\ntwo\nfive\n");
+        assert_eq!(chunker.error_report().errors.len(), 1);
+        assert_eq!(chunker.skipped_ranges(), Some(8..15).as_slice());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_broken_pipe_eof() {
+        struct Pipe<'a>(&'a [u8]);
+
+        impl Read for Pipe<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::from(ErrorKind::BrokenPipe));
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let chunks: Vec<_> = LineChunks::new(4, Pipe(b"a\nb\nc"))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks.concat(), b"a\nb\nc");
+    }
+
+    #[test]
+    fn test_eof_on() {
+        struct Reset<'a>(&'a [u8]);
+
+        impl Read for Reset<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::from(ErrorKind::ConnectionReset));
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let mut chunker = LineChunks::new(4, Reset(b"a\nb\nc"));
+        assert!(chunker.by_ref().any(|chunk| chunk.is_err()));
+
+        let mut chunker = LineChunks::new(4, Reset(b"a\nb\nc"));
+        chunker.eof_on(ErrorKind::ConnectionReset);
+        let chunks: Vec<_> = chunker.by_ref().map(Result::unwrap).collect();
+        assert_eq!(chunks.concat(), b"a\nb\nc");
+        assert_eq!(chunker.ends_with_newline(), Some(false));
+    }
+
+    #[test]
+    fn test_skip_chunks() {
+        let input: String = (0..1000).map(|i| format!("{i:03}\n")).collect();
+        let mut chunker = LineChunks::new(16, io::Cursor::new(input.as_bytes()));
+
+        assert_eq!(chunker.next().unwrap().unwrap(), b"000\n001\n002\n003\n");
+        // Lands exactly on a line start
+        chunker.skip_chunks(2).unwrap();
+        assert!(chunker.next().unwrap().unwrap().starts_with(b"012\n"));
+
+        // Lands mid-line after a short read
+        let mut chunker = LineChunks::new(16, io::Cursor::new(input.as_bytes()));
+        chunker.min_chunk(1);
+        chunker.skip_chunks(1).unwrap();
+        chunker.skip_chunks(1).unwrap();
+        assert!(chunker.next().unwrap().unwrap().starts_with(b"008\n"));
+        chunker.skip_chunks(1000).unwrap();
+        assert!(chunker.next().is_none());
+
+        let mut chunker = LineChunks::new(16, io::Cursor::new(input.as_bytes()));
+        let err = chunker.skip_chunks(usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(chunker.next().unwrap().unwrap().starts_with(b"000\n"));
+    }
+
+    #[test]
+    fn test_ends_with_newline() {
+        for (input, expected) in [
+            (&b"a\nb\n"[..], true),
+            (b"a\nb", false),
+            (b"abcdefgh", false),
+            (b"", true),
+        ] {
+            let mut chunker = LineChunks::new(4, input);
+            assert_eq!(chunker.ends_with_newline(), None);
+            chunker.by_ref().for_each(drop);
+            assert_eq!(chunker.ends_with_newline(), Some(expected), "{input:?}");
+        }
+    }
+
+    #[cfg(feature = "hugepages")]
+    #[test]
+    fn test_hugepages() {
+        let input: String = (0..1_000_000).map(|i| format!("{i}\n")).collect();
+        let chunker = LineChunks::with_hugepages(4 << 20, input.as_bytes());
+
+        let data: Vec<u8> = chunker.flat_map(Result::unwrap).collect();
+        assert_eq!(data, input.as_bytes());
+
+        // Seeking within and past the buffer.
+        let input: String = (0..1000).map(|i| format!("{i:03}\n")).collect();
+        let mut chunker = LineChunks::with_hugepages(16, io::Cursor::new(input.as_bytes()));
+        chunker.min_chunk(1);
+        chunker.skip_chunks(1).unwrap();
+        assert!(chunker.next().unwrap().unwrap().starts_with(b"004\n"));
+        chunker.skip_chunks(100).unwrap();
+        assert!(chunker.next().unwrap().unwrap().starts_with(b"407\n"));
+    }
+
+    #[test]
+    fn test_adaptive_min_chunk() {
+        let line = format!("{}\n", "x".repeat(999));
+        let input = line.repeat(50);
+
+        let fixed = LineChunks::new(256, input.as_bytes()).map(Result::unwrap);
+        assert!(fixed.map(|chunk| chunk.len()).all(|len| len == 1000));
+
+        let mut chunker = LineChunks::new(256, input.as_bytes());
+        chunker.adaptive_min_chunk(true);
+        let lens: Vec<usize> = chunker.map(|chunk| chunk.unwrap().len()).collect();
+
+        // The first chunk is a single line, then they grow to 4 lines each.
+        assert_eq!(lens[..3], [1000, 4000, 4000]);
+        assert_eq!(lens.iter().sum::<usize>(), input.len());
+    }
+
+    #[test]
+    fn test_sequential() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let path = std::env::temp_dir().join(format!("linechunks-seq-{}", std::process::id()));
+        std::fs::write(&path, &input).unwrap();
+
+        let mut chunker = LineChunks::from_path(&path).unwrap();
+        chunker.sequential();
+        let data: Vec<u8> = chunker.flat_map(Result::unwrap).collect();
+        assert_eq!(data, input.as_bytes());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_adaptive_chunk_size() {
+        // Short lines shrink chunks well below the buffer size.
+        let input = "123456789\n".repeat(10_000);
+        let mut chunker = LineChunks::new(64 << 10, input.as_bytes());
+        chunker.adaptive_chunk_size(1 << 10, 8 << 10);
+        let lens: Vec<usize> = chunker.map(|chunk| chunk.unwrap().len()).collect();
+        assert_eq!(lens.iter().sum::<usize>(), input.len());
+        assert!(lens.iter().all(|&len| len <= 8 << 10));
+        assert!(lens[..lens.len() - 1].iter().all(|&len| len >= 6 << 10));
+
+        // Long lines grow them, up to the bound.
+        let input = format!("{}\n", "x".repeat(999)).repeat(100);
+        let mut chunker = LineChunks::new(256, input.as_bytes());
+        chunker.adaptive_chunk_size(256, 16_000);
+        chunker.max_line(1 << 20);
+        let lens: Vec<usize> = chunker.map(|chunk| chunk.unwrap().len()).collect();
+        assert_eq!(lens[..3], [1000, 12_000, 12_000]);
+        assert_eq!(lens.iter().sum::<usize>(), input.len());
+    }
+
+    #[test]
+    fn test_oversize_policy() {
+        let input = "ok\nthis line is too long\nfine\n".as_bytes();
+        let chunks = |policy| {
+            let mut chunker = LineChunks::new(4, input);
+            chunker.max_line(8);
+            chunker.min_chunk(1);
+            chunker.oversize_policy(policy);
+            let chunks: Vec<Vec<u8>> = chunker.by_ref().map(Result::unwrap).collect();
+            (chunks.concat(), chunker.oversized_lines())
+        };
+
+        assert_eq!(chunks(OversizePolicy::SplitMidLine), (input.to_vec(), 2));
+        assert_eq!(
+            chunks(OversizePolicy::SkipLine),
+            (b"ok\nfine\n".to_vec(), 1)
+        );
+        assert_eq!(
+            chunks(OversizePolicy::Truncate),
+            (b"ok\nthis lin\nfine\n".to_vec(), 1)
+        );
+
+        let mut chunker = LineChunks::new(4, input);
+        chunker.max_line(8);
+        assert!(chunker.any(|chunk| chunk.is_err()));
+
+        // Splits back off to the start of a character.
+        let input = "ééééé\n".as_bytes();
+        let mut chunker = LineChunks::new(3, input);
+        chunker.max_line(4);
+        chunker.min_chunk(1);
+        chunker.oversize_policy(OversizePolicy::SplitMidLine);
+        chunker.split_on_char_boundary(true);
+        for chunk in chunker {
+            assert!(std::str::from_utf8(&chunk.unwrap()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_into_parts() {
+        struct Flaky(&'static [u8]);
+        impl Read for Flaky {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::other("gone"));
+                }
+                let len = self.0.len().min(buf.len()).min(5);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let mut chunker = LineChunks::new(5, Flaky(b"one\ntwo\nthr"));
+        chunker.min_chunk(1);
+        assert_eq!(chunker.next().unwrap().unwrap(), b"one\n");
+        assert_eq!(chunker.next().unwrap().unwrap(), b"two\n");
+        assert!(chunker.next().unwrap().is_err());
+
+        let (_, pending) = chunker.into_parts();
+        assert_eq!(pending, b"thr");
+
+        let mut chunker = LineChunks::new(4, &b"ab\ncdefghijkl\nmn\n"[..]);
+        chunker.max_line(6);
+        chunker.min_chunk(8);
+        let err = chunker.find_map(Result::err).unwrap();
+        match LineChunksError::from(err) {
+            LineChunksError::MaxLineExceeded {
+                offset, partial, ..
+            } => {
+                assert_eq!(offset, 3);
+                assert!(b"cdefghijkl".starts_with(&partial));
+            }
+            err => panic!("unexpected {err}"),
+        }
+        let (_, pending) = chunker.into_parts();
+        assert_eq!(&pending[..3], b"ab\n");
+    }
+
+    #[test]
+    fn test_retry() {
+        // Fails each read `fails` times with `kind` before succeeding.
+        struct Transient {
+            data: &'static [u8],
+            kind: ErrorKind,
+            fails: u32,
+            failed: u32,
+        }
+        impl Read for Transient {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.failed < self.fails {
+                    self.failed += 1;
+                    return Err(self.kind.into());
+                }
+                self.failed = 0;
+                let len = self.data.len().min(buf.len());
+                buf[..len].copy_from_slice(&self.data[..len]);
+                self.data = &self.data[len..];
+                Ok(len)
+            }
+        }
+        let transient = |kind, fails| Transient {
+            data: b"one\ntwo\nthree\n",
+            kind,
+            fails,
+            failed: 0,
+        };
+
+        let chunker = LineChunks::new(4, transient(ErrorKind::Interrupted, 2));
+        let chunks: io::Result<Vec<_>> = chunker.collect();
+        assert_eq!(chunks.unwrap().concat(), b"one\ntwo\nthree\n");
+
+        let mut chunker = LineChunks::new(4, transient(ErrorKind::WouldBlock, 2));
+        chunker.retry_if(|err, retries| err.kind() == ErrorKind::WouldBlock && retries < 2);
+        let chunks: io::Result<Vec<_>> = chunker.collect();
+        assert_eq!(chunks.unwrap().concat(), b"one\ntwo\nthree\n");
+
+        let mut chunker = LineChunks::new(4, transient(ErrorKind::WouldBlock, 3));
+        chunker.retry_if(|_, retries| retries < 2);
+        assert!(chunker.collect::<io::Result<Vec<_>>>().is_err());
+    }
+
+    #[test]
+    fn test_recoverable() {
+        // Fails every other read.
+        struct Flaky(&'static [u8], bool);
+        impl Read for Flaky {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 = !self.1;
+                if self.1 {
+                    return Err(io::Error::other("flaky"));
+                }
+                let len = self.0.len().min(buf.len()).min(3);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let mut chunker = LineChunks::new(4, Flaky(b"one\ntwo\nthree\n", false));
+        chunker.min_chunk(1);
+        chunker.recoverable(true);
+
+        let (mut data, mut errors) = (Vec::new(), 0);
+        for chunk in chunker {
+            match chunk {
+                Ok(chunk) => data.extend(chunk),
+                Err(_) => errors += 1,
+            }
+        }
+        assert_eq!(data, b"one\ntwo\nthree\n");
+        assert!(errors > 3);
+
+        let mut chunker = LineChunks::new(4, &b"ab\ncdefghijkl\nmn\n"[..]);
+        chunker.max_line(6);
+        chunker.min_chunk(1);
+        chunker.recoverable(true);
+        let chunks: Vec<_> = chunker.map(|chunk| chunk.ok()).collect();
+        assert_eq!(
+            chunks,
+            [Some(b"ab\n".to_vec()), None, Some(b"mn\n".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let input = &b"one\ntwo\nthree\n\x00\x01binary"[..];
+        let mut chunker = LineChunks::new(8, input);
+        chunker.min_chunk(1);
+        assert_eq!(chunker.get_ref().len(), input.len());
+
+        let lines: Vec<_> = chunker.by_ref().take(1).map(Result::unwrap).collect();
+        assert_eq!(lines.concat(), b"one\ntwo\n");
+
+        let mut rest = Vec::new();
+        chunker.into_inner().read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"three\n\x00\x01binary");
+    }
+
+    #[test]
+    fn test_from_buf_read() {
+        use std::io::BufReader;
+
+        let input: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        let mut read = BufReader::with_capacity(100, input.as_bytes());
+
+        // Read a header line first, then chunk the rest from the same buffer.
+        let mut header = String::new();
+        read.read_line(&mut header).unwrap();
+        assert_eq!(header, "line 0\n");
+
+        let mut chunker = LineChunks::from_buf_read(256, &mut read);
+        let chunks: Vec<_> = chunker.by_ref().map(Result::unwrap).collect();
+        assert!(chunks.iter().all(|chunk| chunk.ends_with(b"\n")));
+        assert_eq!(chunks.concat(), &input.as_bytes()[header.len()..]);
+        assert!(chunks.iter().all(|chunk| chunk.len() >= 192));
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let input: String = (0..1000).map(|i| format!("{i:03}\n")).collect();
+        let mut chunker = LineChunks::new(100, input.as_bytes());
+        assert_eq!(chunker.size_hint(), (0, None));
+
+        chunker.len_hint(input.len() as u64);
+        let (_, upper) = chunker.size_hint();
+        assert!(upper.unwrap() >= 40);
+
+        let mut chunks = 0;
+        while chunker.next().is_some() {
+            chunks += 1;
+            let (_, upper) = chunker.size_hint();
+            assert!(upper.unwrap() >= 40 - chunks);
+        }
+        assert_eq!(chunks, 40);
+        assert_eq!(chunker.size_hint(), (0, Some(0)));
+
+        let file = File::open("/usr/share/dict/words").unwrap();
+        let chunker = LineChunks::from_file(1 << 16, file).unwrap();
+        let (_, upper) = chunker.size_hint();
+        assert!(chunker.count() <= upper.unwrap());
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(auto_chunksize(0, 4096), 64 << 10);
+        assert_eq!(auto_chunksize(64 << 20, 4096), 1 << 20);
+        assert_eq!(auto_chunksize(1 << 40, 4096), 4 << 20);
+        assert_eq!(auto_chunksize(100 << 20, 1 << 20), 2 << 20);
+
+        let chunker = LineChunks::from_path("/usr/share/dict/words").unwrap();
+        assert!(chunker.size_hint().1.is_some());
+        assert!(chunker
+            .map(Result::unwrap)
+            .all(|chunk| chunk.ends_with(b"\n")));
+        assert!(LineChunks::from_path("/nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_with_initial() {
+        let mut read = &b"# header\npartial line\nnext\nlast"[..];
+        let mut header = [0; 12];
+        read.read_exact(&mut header).unwrap();
+
+        // The header line is complete, but "par" isn't.
+        let prefix = header[9..].to_vec();
+        let mut chunker = LineChunks::with_initial(4, prefix, read).offsets();
+        let chunks: Vec<_> = chunker.by_ref().map(Result::unwrap).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                (0, b"partial line\n".to_vec()),
+                (13, b"next\n".to_vec()),
+                (18, b"last".to_vec())
+            ]
+        );
+
+        let chunker = LineChunks::with_initial(4, b"only\n".to_vec(), &b""[..]);
+        assert_eq!(chunker.map(Result::unwrap).collect::<Vec<_>>(), [b"only\n"]);
+    }
+
+    #[test]
+    fn test_on_chunk() {
+        let input = b"one\ntwo\nthree\nfour\nfive";
+        let infos = Arc::new(Mutex::new(Vec::new()));
+        let mut chunker = LineChunks::new(8, &input[..]);
+        chunker.on_chunk({
+            let infos = infos.clone();
+            move |info| infos.lock().unwrap().push(info.clone())
+        });
+
+        let chunks: Vec<Vec<u8>> = chunker.map(Result::unwrap).collect();
+        let infos = infos.lock().unwrap();
+        assert_eq!(infos.len(), chunks.len());
+        assert_eq!(
+            infos[0],
+            ChunkInfo {
+                index: 0,
+                offset: 0,
+                len: 8,
+                lines: 2
+            }
+        );
+
+        let mut offset = 0;
+        for (info, chunk) in infos.iter().zip(&chunks) {
+            assert_eq!(info.offset, offset);
+            assert_eq!(info.len, chunk.len());
+            offset += chunk.len() as u64;
+        }
+        assert_eq!(infos.last().unwrap().lines, 2);
+        assert_eq!(infos.iter().map(|info| info.lines).sum::<u64>(), 5);
+    }
+
+    #[test]
+    fn test_delimiter() {
+        let input = b"one\0two\nstill two\0three";
+        let mut chunker = LineChunks::with_delimiter(6, &input[..], Byte::<0>);
+        chunker.min_chunk(1);
+
+        let chunks: Vec<_> = chunker.by_ref().map(Result::unwrap).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                b"one\0".to_vec(),
+                b"two\nstill two\0".to_vec(),
+                b"three".to_vec()
+            ]
+        );
+        assert_eq!(chunker.ends_with_newline(), Some(false));
+    }
+
+    #[test]
+    fn test_split_round_trip() {
+        let chunk = b"one\n\ntwo\n\nthree".to_vec();
+        let split = |terminator, empty| {
+            let mut split = LineSplitParse::new(chunk.clone(), <[u8]>::to_vec);
+            split.keep_terminator(terminator);
+            split.keep_empty(empty);
+            split.collect::<Vec<_>>()
+        };
+
+        assert_eq!(split(false, false), [&b"one"[..], b"two", b"three"]);
+        assert_eq!(
+            split(false, true),
+            [&b"one"[..], b"", b"two", b"", b"three"]
+        );
+        assert_eq!(split(true, false), [&b"one\n"[..], b"two\n", b"three"]);
+        assert_eq!(split(true, true).concat(), chunk);
+    }
+
+    #[test]
+    fn test_split_trim() {
+        let chunk = b"one\r\n  two \r\n\r\n three\r".to_vec();
+
+        let mut split = LineSplitParse::new(chunk.clone(), <[u8]>::to_vec);
+        split.trim_crlf(true);
+        let lines: Vec<_> = split.collect();
+        assert_eq!(lines, [&b"one"[..], b"  two ", b" three"]);
+
+        let mut split = LineSplitParse::new(chunk, <[u8]>::to_vec);
+        split.trim_whitespace(true);
+        split.keep_empty(true);
+        let lines: Vec<_> = split.collect();
+        assert_eq!(lines, [&b"one"[..], b"two", b"", b"three"]);
+    }
+
+    #[test]
+    fn test_runtime_delimiter() {
+        let input = b"a.txt\0dir/b c.txt\0\0last";
+        let delim: u8 = 0;
+        let mut chunker = LineChunks::with_delimiter(4, &input[..], delim);
+        chunker.min_chunk(1);
+
+        let names: Vec<String> = chunker
+            .flat_map(|chunk| {
+                let mut split = LineSplitParse::new(chunk.unwrap(), |name: &[u8]| {
+                    String::from_utf8_lossy(name).into_owned()
+                });
+                split.delimiter(delim);
+                split
+            })
+            .collect();
+        assert_eq!(names, ["a.txt", "dir/b c.txt", "last"]);
+    }
+
     #[test]
     fn test_words() {
         let file = File::open("/usr/share/dict/words").expect("/dev/zero open failed");
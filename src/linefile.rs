@@ -0,0 +1,275 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::LineIndex;
+
+/// A file with random access by line number.
+///
+/// Lines are found with a [`LineIndex`], which is built when the file is
+/// opened or can be loaded from a saved copy. Reads are positioned by the
+/// index, so fetching a line or range of lines only reads those bytes, and
+/// any number of threads can read at once.
+///
+/// With the `mmap` feature, the file is memory-mapped, so it must not be
+/// modified or truncated while it's open. Otherwise each read is a
+/// positioned read of the file.
+pub struct LineFile {
+    path: PathBuf,
+    #[cfg(feature = "mmap")]
+    map: memmap2::Mmap,
+    #[cfg(not(feature = "mmap"))]
+    file: File,
+    index: LineIndex,
+}
+
+#[cfg(feature = "mmap")]
+fn backing(file: File) -> io::Result<memmap2::Mmap> {
+    // SAFETY: it's up to the user not to modify the file while it's open, as
+    // documented above.
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+#[cfg(not(feature = "mmap"))]
+fn backing(file: File) -> io::Result<File> {
+    Ok(file)
+}
+
+#[cfg(all(unix, not(feature = "mmap")))]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(all(windows, not(feature = "mmap")))]
+fn read_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(read) => {
+                buf = &mut buf[read..];
+                offset += read as u64;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+impl LineFile {
+    /// Open `path`, building its index.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<LineFile> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+        let index = LineIndex::build(&mut file)?;
+
+        Ok(LineFile {
+            path: path.to_path_buf(),
+            #[cfg(feature = "mmap")]
+            map: backing(file)?,
+            #[cfg(not(feature = "mmap"))]
+            file: backing(file)?,
+            index,
+        })
+    }
+
+    /// Open `path` with a previously saved index. Returns an
+    /// [`ErrorKind::InvalidData`] error if the file's length has changed
+    /// since the index was built.
+    pub fn with_index<P: AsRef<Path>>(path: P, index: LineIndex) -> io::Result<LineFile> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        if file.metadata()?.len() != index.file_len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "line index is stale",
+            ));
+        }
+
+        Ok(LineFile {
+            path: path.to_path_buf(),
+            #[cfg(feature = "mmap")]
+            map: backing(file)?,
+            #[cfg(not(feature = "mmap"))]
+            file: backing(file)?,
+            index,
+        })
+    }
+
+    /// The file's path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file's index, for example to save it.
+    pub fn index(&self) -> &LineIndex {
+        &self.index
+    }
+
+    /// Number of lines in the file.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// True if the file has no lines.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read line `line` (counting from 0), without its `\n`. Returns `None` if
+    /// it's past the end of the file.
+    pub fn get(&self, line: usize) -> io::Result<Option<Vec<u8>>> {
+        let Some(end) = line.checked_add(1) else {
+            return Ok(None);
+        };
+        let Some(mut line) = self.range(line..end)? else {
+            return Ok(None);
+        };
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    /// Read lines `lines` as one line-aligned chunk, including their `\n`s.
+    /// Returns `None` if the range is out of bounds.
+    pub fn range(&self, lines: Range<usize>) -> io::Result<Option<Vec<u8>>> {
+        let Some(range) = self.index.range(lines) else {
+            return Ok(None);
+        };
+        self.read(range).map(Some)
+    }
+
+    /// Read the bytes `range`, which the index says are in the file.
+    #[cfg(feature = "mmap")]
+    fn read(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        Ok(self.map[range.start as usize..range.end as usize].to_vec())
+    }
+
+    /// Read the bytes `range`, which the index says are in the file.
+    #[cfg(not(feature = "mmap"))]
+    fn read(&self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; (range.end - range.start) as usize];
+        read_at(&self.file, &mut buf, range.start)?;
+        Ok(buf)
+    }
+
+    /// Process lines `lines` in parallel, in chunks of `chunk_lines` lines.
+    ///
+    /// `f` is called with each chunk's line range and its data, in no
+    /// particular order. Returns the first error from reading the file or from
+    /// `f`, or an [`ErrorKind::InvalidInput`] error if `lines` is out of
+    /// bounds.
+    #[cfg(feature = "rayon")]
+    pub fn for_each_range<F>(&self, lines: Range<usize>, chunk_lines: usize, f: F) -> io::Result<()>
+    where
+        F: Fn(Range<usize>, Vec<u8>) -> io::Result<()> + Sync,
+    {
+        use rayon::prelude::*;
+
+        use crate::shutdown::Shutdown;
+
+        if self.index.range(lines.clone()).is_none() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "line range out of bounds",
+            ));
+        }
+        let chunk_lines = chunk_lines.max(1);
+        let shutdown = Shutdown::default();
+
+        lines
+            .clone()
+            .step_by(chunk_lines)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .try_for_each(|start| {
+                if shutdown.is_stopped() {
+                    return Ok(());
+                }
+                let chunk = start..start.saturating_add(chunk_lines).min(lines.end);
+                let data = self.read(self.index.range(chunk.clone()).expect("range checked"))?;
+
+                let _guard = shutdown.guard();
+                f(chunk, data)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn write(name: &str) -> (PathBuf, String) {
+        let path = env::temp_dir().join(format!("linechunks-{name}-{}", process::id()));
+        let input: String = (0..10_000).map(|i| format!("line {i}\n")).collect();
+        fs::write(&path, &input).unwrap();
+        (path, input)
+    }
+
+    #[test]
+    fn test_line_file() {
+        let (path, _) = write("linefile");
+        let file = LineFile::open(&path).unwrap();
+
+        assert_eq!(file.len(), 10_000);
+        assert_eq!(file.get(0).unwrap().unwrap(), b"line 0");
+        assert_eq!(file.get(9999).unwrap().unwrap(), b"line 9999");
+        assert_eq!(file.get(10_000).unwrap(), None);
+        assert_eq!(file.get(usize::MAX).unwrap(), None);
+        assert_eq!(file.range(5..7).unwrap().unwrap(), b"line 5\nline 6\n");
+
+        let reopened = LineFile::with_index(&path, file.index().clone()).unwrap();
+        assert_eq!(reopened.get(42).unwrap().unwrap(), b"line 42");
+
+        fs::write(&path, "changed\n").unwrap();
+        let stale = LineFile::with_index(&path, file.index().clone());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(stale.err().unwrap().kind(), ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_for_each_range() {
+        use std::sync::Mutex;
+
+        let (path, input) = write("linefile-par");
+        let file = LineFile::open(&path).unwrap();
+
+        let chunks = Mutex::new(Vec::new());
+        file.for_each_range(100..9000, 128, |lines, data| {
+            chunks.lock().unwrap().push((lines.start, data));
+            Ok(())
+        })
+        .unwrap();
+
+        // A huge chunk size is one chunk.
+        file.for_each_range(0..10, usize::MAX, |lines, data| {
+            assert_eq!(lines, 0..10);
+            assert!(data.ends_with(b"line 9\n"));
+            Ok(())
+        })
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut chunks = chunks.into_inner().unwrap();
+        chunks.sort();
+        let data: Vec<u8> = chunks.into_iter().flat_map(|(_, data)| data).collect();
+        let expected: String = input
+            .lines()
+            .skip(100)
+            .take(8900)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert_eq!(data, expected.as_bytes());
+    }
+}
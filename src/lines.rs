@@ -0,0 +1,224 @@
+use std::io::{self, ErrorKind, Read};
+
+use crate::{splitter::Splitter, utf8, LineChunks};
+
+/// Iterate over the lines of a stream of chunks, without their `\n`.
+///
+/// Empty lines are skipped, as with [`LineSplitParse`](crate::LineSplitParse).
+pub(crate) struct ChunkLines<I> {
+    chunks: I,
+    chunk: Vec<u8>,
+    split: Splitter,
+}
+
+impl<I> ChunkLines<I> {
+    pub(crate) fn new(chunks: I) -> Self {
+        ChunkLines {
+            chunks,
+            chunk: Vec::new(),
+            split: Splitter::new(0),
+        }
+    }
+}
+
+impl<I, E> Iterator for ChunkLines<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    type Item = Result<Vec<u8>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, range)) = self.split.next_line(&self.chunk) {
+                break Some(Ok(self.chunk[range].to_vec()));
+            }
+
+            match self.chunks.next()? {
+                Ok(chunk) => {
+                    self.split = Splitter::new(chunk.len());
+                    self.chunk = chunk;
+                }
+                Err(err) => break Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Iterator over the lines of a [`LineChunks`] as byte vectors, like
+/// [`BufRead::split`](std::io::BufRead::split) on `\n` but also stripping a
+/// trailing `\r`. Created by [`LineChunks::byte_lines`].
+pub struct ByteLines<R> {
+    chunks: LineChunks<R>,
+    chunk: Vec<u8>,
+    split: Splitter,
+}
+
+impl<R: Read> Iterator for ByteLines<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(span) = self.split.next_span(&self.chunk) {
+                // Like `BufRead::lines`, only strip a `\r` which is part of
+                // a `\r\n`.
+                let mut eol = span.eol;
+                if eol < span.next && eol > span.start && self.chunk[eol - 1] == b'\r' {
+                    eol -= 1;
+                }
+                break Some(Ok(self.chunk[span.start..eol].to_vec()));
+            }
+
+            match self.chunks.next()? {
+                Ok(chunk) => {
+                    self.split = Splitter::new(chunk.len());
+                    self.chunk = chunk;
+                }
+                Err(err) => break Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Iterator over the lines of a [`LineChunks`] as `String`s. Created by
+/// [`LineChunks::lines`].
+///
+/// This behaves like [`BufRead::lines`](std::io::BufRead::lines): each line
+/// has its `\n` or `\r\n` stripped, empty lines are included, and a line
+/// which isn't valid UTF-8 is an [`ErrorKind::InvalidData`] error. With the
+/// `simdutf8` feature, validation is SIMD accelerated.
+pub struct Lines<R> {
+    lines: ByteLines<R>,
+}
+
+impl<R: Read> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        Some(line.and_then(|line| {
+            utf8::into_string(line).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+        }))
+    }
+}
+
+/// Iterator over the parsed lines of a [`LineChunks`]. Created by
+/// [`LineChunks::map_lines`].
+pub struct MapLines<R, F> {
+    chunks: LineChunks<R>,
+    chunk: Vec<u8>,
+    split: Splitter,
+    parser: F,
+}
+
+impl<R: Read, F, T> Iterator for MapLines<R, F>
+where
+    F: FnMut(&[u8]) -> T,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, range)) = self.split.next_line(&self.chunk) {
+                break Some(Ok((self.parser)(&self.chunk[range])));
+            }
+
+            match self.chunks.next()? {
+                Ok(chunk) => {
+                    self.split = Splitter::new(chunk.len());
+                    self.chunk = chunk;
+                }
+                Err(err) => break Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl<R: Read> LineChunks<R> {
+    /// Parse each line with `parser`, yielding the results directly rather
+    /// than chunk by chunk. Lines are passed without their `\n`, and empty
+    /// lines are skipped, as with [`LineSplitParse`](crate::LineSplitParse).
+    /// Input is still read a chunk at a time, and lines are parsed in place
+    /// without copying them.
+    ///
+    /// ```
+    /// let input = &b"1\n2\n\n3\n"[..];
+    /// let sum: u32 = linechunks::LineChunks::new(8192, input)
+    ///     .map_lines(|line| std::str::from_utf8(line).unwrap().parse::<u32>().unwrap())
+    ///     .sum::<std::io::Result<u32>>()?;
+    /// assert_eq!(sum, 6);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn map_lines<F, T>(self, parser: F) -> MapLines<R, F>
+    where
+        F: FnMut(&[u8]) -> T,
+    {
+        MapLines {
+            chunks: self,
+            chunk: Vec::new(),
+            split: Splitter::new(0),
+            parser,
+        }
+    }
+
+    /// Iterate over individual lines as `String`s, as a faster drop-in
+    /// replacement for [`BufRead::lines`](std::io::BufRead::lines).
+    pub fn lines(self) -> Lines<R> {
+        Lines {
+            lines: self.byte_lines(),
+        }
+    }
+
+    /// Iterate over individual lines as byte vectors, without their `\n` or
+    /// `\r\n`.
+    pub fn byte_lines(self) -> ByteLines<R> {
+        ByteLines {
+            chunks: self,
+            chunk: Vec::new(),
+            split: Splitter::new(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufRead;
+
+    use super::*;
+
+    #[test]
+    fn test_map_lines() {
+        let input: String = (0..1000).map(|i| format!("{i}\n\n")).collect();
+        let mut chunks = LineChunks::new(64, input.as_bytes());
+        chunks.min_chunk(1);
+
+        let lens: Vec<usize> = chunks.map_lines(<[u8]>::len).map(Result::unwrap).collect();
+        assert_eq!(lens.len(), 1000);
+        assert_eq!(lens[999], 3);
+    }
+
+    #[test]
+    fn test_lines() {
+        for input in [
+            &b"one\ntwo\r\n\nthree"[..],
+            b"one\n\n\ntwo\n",
+            b"\n",
+            b"",
+            b"a very long line which spans several chunks\r\nshort\n",
+            b"tail\r",
+        ] {
+            let expected: Vec<String> = input.lines().map(Result::unwrap).collect();
+            let got: Vec<String> = LineChunks::new(4, input)
+                .lines()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(got, expected, "{input:?}");
+        }
+
+        let mut lines = LineChunks::new(4, &b"ok\n\xff\n"[..]).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "ok");
+        assert_eq!(
+            lines.next().unwrap().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
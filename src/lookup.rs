@@ -0,0 +1,167 @@
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+use crate::split::next_line_start;
+
+/// Read the line starting at `pos`, returning it without its `\n` and the
+/// offset of the following line.
+fn line_at<R: Read + Seek>(reader: &mut R, pos: u64) -> io::Result<(Vec<u8>, u64)> {
+    reader.seek(SeekFrom::Start(pos))?;
+    let mut line = Vec::new();
+    let len = BufReader::new(reader).read_until(b'\n', &mut line)?;
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+
+    Ok((line, pos + len as u64))
+}
+
+/// Find the offset of the first line for which `less` is false, assuming the
+/// lines are sorted so that all the lines for which it's true come first.
+fn lower_bound<R, F>(reader: &mut R, less: F) -> io::Result<u64>
+where
+    R: Read + Seek,
+    F: Fn(&[u8]) -> bool,
+{
+    let len = reader.seek(SeekFrom::End(0))?;
+
+    // `lo` is always a line start, and every line before it is less.
+    let mut lo = 0;
+    let mut hi = len;
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let start = next_line_start(reader, mid)?;
+
+        if start >= hi {
+            // No line starts between mid and hi
+            hi = mid;
+            continue;
+        }
+
+        let (line, next) = line_at(reader, start)?;
+        if less(&line) {
+            lo = next;
+        } else {
+            hi = start;
+        }
+    }
+
+    // We're now within a line or two of the boundary, so finish with a
+    // linear scan.
+    while lo < len {
+        let (line, next) = line_at(reader, lo)?;
+        if !less(&line) {
+            break;
+        }
+        lo = next;
+    }
+
+    Ok(lo)
+}
+
+/// Read lines from `pos` for as long as they match.
+fn matching<R, F>(reader: &mut R, pos: u64, matches: F) -> io::Result<Vec<Vec<u8>>>
+where
+    R: Read + Seek,
+    F: Fn(&[u8]) -> bool,
+{
+    reader.seek(SeekFrom::Start(pos))?;
+    let mut reader = BufReader::new(reader);
+    let mut lines = Vec::new();
+
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        if !matches(&line) {
+            break;
+        }
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// Find all the lines with a given key in a sorted input, like `look(1)`.
+///
+/// The input must be sorted bytewise by the key returned by `key_of` for each
+/// line. Rather than scanning, this bisects the input by seeking to a byte
+/// offset and realigning to the next line boundary, so only a logarithmic
+/// number of lines are read before the matching ones. Returns the matching
+/// lines without their `\n`.
+pub fn sorted_lookup<R, K>(reader: &mut R, key: &[u8], key_of: K) -> io::Result<Vec<Vec<u8>>>
+where
+    R: Read + Seek,
+    K: Fn(&[u8]) -> &[u8],
+{
+    let start = lower_bound(reader, |line| key_of(line) < key)?;
+    matching(reader, start, |line| key_of(line) == key)
+}
+
+/// Find all the lines starting with `prefix` in a bytewise sorted input.
+///
+/// See [`sorted_lookup`].
+pub fn sorted_range<R>(reader: &mut R, prefix: &[u8]) -> io::Result<Vec<Vec<u8>>>
+where
+    R: Read + Seek,
+{
+    let start = lower_bound(reader, |line| line < prefix)?;
+    matching(reader, start, |line| line.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn input() -> Cursor<Vec<u8>> {
+        let mut words = Vec::new();
+        for i in 0..2000u32 {
+            for dup in 0..(i % 3) {
+                words.extend_from_slice(format!("{i:05}\tvalue{dup}\n").as_bytes());
+            }
+        }
+        Cursor::new(words)
+    }
+
+    fn field(line: &[u8]) -> &[u8] {
+        line.split(|&c| c == b'\t').next().unwrap()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut input = input();
+
+        assert_eq!(
+            sorted_lookup(&mut input, b"00011", field).unwrap(),
+            vec![b"00011\tvalue0".to_vec(), b"00011\tvalue1".to_vec()]
+        );
+        assert!(sorted_lookup(&mut input, b"00012", field)
+            .unwrap()
+            .is_empty());
+        assert_eq!(sorted_lookup(&mut input, b"00001", field).unwrap().len(), 1);
+        assert_eq!(sorted_lookup(&mut input, b"01999", field).unwrap().len(), 1);
+        assert!(sorted_lookup(&mut input, b"99999", field)
+            .unwrap()
+            .is_empty());
+        assert!(sorted_lookup(&mut input, b"", field).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut input = input();
+
+        let lines = sorted_range(&mut input, b"0010").unwrap();
+        assert_eq!(lines.len(), 10);
+        assert!(lines.iter().all(|line| line.starts_with(b"0010")));
+
+        assert_eq!(sorted_range(&mut input, b"").unwrap().len(), 1999);
+        let mut empty = Cursor::new(Vec::new());
+        assert!(sorted_range(&mut empty, b"x").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+/// Apply a fallible transform to each chunk, such as decrypting or
+/// decompressing it.
+///
+/// Errors from the input and from `f` end iteration: the first one is
+/// returned, and after that the iterator is finished. `f` can return anything,
+/// but it should keep chunks line-aligned if later stages rely on that.
+pub struct MapChunks<I, F> {
+    chunks: I,
+    f: F,
+    finished: bool,
+}
+
+impl<I, F, T, E> MapChunks<I, F>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+    F: FnMut(Vec<u8>) -> Result<T, E>,
+{
+    pub fn new(chunks: I, f: F) -> Self {
+        MapChunks {
+            chunks,
+            f,
+            finished: false,
+        }
+    }
+}
+
+impl<I, F, T, E> Iterator for MapChunks<I, F>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+    F: FnMut(Vec<u8>) -> Result<T, E>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let res = self.chunks.next()?.and_then(&mut self.f);
+        self.finished = res.is_err();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_map_chunks() {
+        let input = &b"a\nb\nc\nd\n"[..];
+        let upper = |chunk: Vec<u8>| Ok(chunk.to_ascii_uppercase());
+        let chunks: Vec<_> = MapChunks::new(LineChunks::new(2, input), upper)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks.concat(), b"A\nB\nC\nD\n");
+
+        let mut seen = 0;
+        let failing = |chunk: Vec<u8>| {
+            seen += 1;
+            if chunk == b"b\n" {
+                return Err(io::Error::other("bad chunk"));
+            }
+            Ok(chunk)
+        };
+        let results: Vec<_> = MapChunks::new(LineChunks::new(2, input), failing).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+        assert_eq!(seen, 2);
+    }
+}
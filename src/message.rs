@@ -0,0 +1,134 @@
+use std::mem;
+
+use crate::LineSplitParse;
+
+/// A destination for batches of messages, such as a Kafka, NATS or HTTP
+/// producer.
+///
+/// `send_batch` should block until the batch has been accepted, which gives
+/// backpressure: a [`Batcher`] won't read further ahead than the sink allows.
+pub trait MessageSink<T> {
+    type Error;
+
+    /// Send a batch of messages, in order.
+    fn send_batch(&mut self, batch: Vec<T>) -> Result<(), Self::Error>;
+
+    /// Wait for everything sent so far to be delivered.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Batch the lines of each chunk into a [`MessageSink`].
+///
+/// Each non-empty line is parsed into a message, or skipped if the parser
+/// returns `None`. Messages are sent in batches of up to `max_batch`, and
+/// batches never span chunks, so each chunk is delivered as a whole number of
+/// batches.
+pub struct Batcher<S, T> {
+    sink: S,
+    max_batch: usize,
+    batch: Vec<T>,
+}
+
+impl<S, T> Batcher<S, T>
+where
+    S: MessageSink<T>,
+{
+    /// Panics if `max_batch` is 0.
+    pub fn new(sink: S, max_batch: usize) -> Self {
+        assert!(max_batch > 0, "batch size must be non-zero");
+
+        Batcher {
+            sink,
+            max_batch,
+            batch: Vec::new(),
+        }
+    }
+
+    fn send(&mut self) -> Result<(), S::Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = mem::replace(&mut self.batch, Vec::with_capacity(self.max_batch));
+        self.sink.send_batch(batch)
+    }
+
+    /// Parse the lines of `chunk` and send them.
+    pub fn send_chunk<F>(&mut self, chunk: Vec<u8>, parse: F) -> Result<(), S::Error>
+    where
+        F: FnMut(&[u8]) -> Option<T>,
+    {
+        for msg in LineSplitParse::new(chunk, parse).flatten() {
+            self.batch.push(msg);
+            if self.batch.len() == self.max_batch {
+                self.send()?;
+            }
+        }
+        self.send()
+    }
+
+    /// Flush the sink and return it.
+    pub fn finish(mut self) -> Result<S, S::Error> {
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    #[derive(Default)]
+    struct Collect {
+        batches: Vec<Vec<String>>,
+        flushed: bool,
+    }
+
+    impl MessageSink<String> for Collect {
+        type Error = String;
+
+        fn send_batch(&mut self, batch: Vec<String>) -> Result<(), String> {
+            if batch.iter().any(|msg| msg == "bad") {
+                return Err("rejected".to_string());
+            }
+            self.batches.push(batch);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), String> {
+            self.flushed = true;
+            Ok(())
+        }
+    }
+
+    fn parse(line: &[u8]) -> Option<String> {
+        String::from_utf8(line.to_vec()).ok()
+    }
+
+    #[test]
+    fn test_batcher() {
+        let input = &b"a\nb\nc\nd\ne\nf\ng\n"[..];
+        let mut batcher = Batcher::new(Collect::default(), 2);
+        let mut chunks = LineChunks::new(8, input);
+        chunks.min_chunk(1);
+
+        for chunk in chunks {
+            batcher.send_chunk(chunk.unwrap(), parse).unwrap();
+        }
+        let sink = batcher.finish().unwrap();
+
+        assert!(sink.flushed);
+        assert_eq!(
+            sink.batches,
+            vec![vec!["a", "b"], vec!["c", "d"], vec!["e", "f"], vec!["g"]]
+        );
+
+        let mut batcher = Batcher::new(Collect::default(), 2);
+        assert_eq!(
+            batcher.send_chunk(b"ok\nbad\n".to_vec(), parse),
+            Err("rejected".to_string())
+        );
+    }
+}
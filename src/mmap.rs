@@ -0,0 +1,140 @@
+use std::{fs::File, io, ops::Deref, ops::Range, path::Path, sync::Arc};
+
+use memmap2::Mmap;
+
+use crate::SliceChunks;
+
+/// Line-aligned chunks of a memory-mapped file, without copying.
+///
+/// Each chunk is an [`MmapChunk`], a cheap handle onto a range of the shared
+/// map, so chunks can be sent to other threads, for example with rayon's
+/// `par_bridge`, and kept as long as needed. Chunks are sized as for
+/// [`SliceChunks`].
+///
+/// The file must not be modified or truncated while it's mapped, or the
+/// chunks' contents may change under them, or reading them may crash.
+pub struct MmapLineChunks {
+    map: Arc<Mmap>,
+    pos: usize,
+    chunksize: usize,
+    min_chunk: usize,
+}
+
+impl MmapLineChunks {
+    /// Map the file at `path`.
+    pub fn open(chunksize: usize, path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(chunksize, &File::open(path)?)
+    }
+
+    /// Map `file`.
+    pub fn new(chunksize: usize, file: &File) -> io::Result<Self> {
+        // SAFETY: it's up to the user not to modify the file while it's
+        // mapped, as documented above.
+        let map = unsafe { Mmap::map(file)? };
+        Ok(MmapLineChunks {
+            map: Arc::new(map),
+            pos: 0,
+            chunksize,
+            min_chunk: chunksize * 3 / 4,
+        })
+    }
+
+    /// Minimum chunk size. The last chunk may be shorter.
+    pub fn min_chunk(&mut self, size: usize) {
+        self.min_chunk = size;
+    }
+
+    /// The whole map.
+    pub fn map(&self) -> &Arc<Mmap> {
+        &self.map
+    }
+}
+
+impl Iterator for MmapLineChunks {
+    type Item = MmapChunk;
+
+    fn next(&mut self) -> Option<MmapChunk> {
+        let mut chunks = SliceChunks::new(self.chunksize, &self.map[self.pos..]);
+        chunks.min_chunk(self.min_chunk);
+        let len = chunks.next()?.len();
+
+        let range = self.pos..self.pos + len;
+        self.pos = range.end;
+        Some(MmapChunk {
+            map: self.map.clone(),
+            range,
+        })
+    }
+}
+
+/// A chunk of a memory-mapped file, from [`MmapLineChunks`].
+///
+/// This dereferences to the chunk's bytes, and keeps the map alive. With the
+/// `bytes` feature, it can be converted to a `Bytes` without copying.
+#[derive(Clone)]
+pub struct MmapChunk {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+}
+
+impl MmapChunk {
+    /// The chunk's byte range in the file.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+}
+
+impl Deref for MmapChunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+}
+
+impl AsRef<[u8]> for MmapChunk {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<MmapChunk> for bytes::Bytes {
+    fn from(chunk: MmapChunk) -> Self {
+        bytes::Bytes::from_owner(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_mmap_chunks() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let path = env::temp_dir().join(format!("linechunks-mmap-{}", process::id()));
+        fs::write(&path, &input).unwrap();
+
+        let chunks: Vec<MmapChunk> = MmapLineChunks::open(64, &path).unwrap().collect();
+        let mut pos = 0;
+        for chunk in &chunks {
+            assert_eq!(chunk.range().start, pos);
+            assert!(chunk.ends_with(b"\n"));
+            pos = chunk.range().end;
+        }
+        let data: Vec<&[u8]> = chunks.iter().map(|chunk| &chunk[..]).collect();
+        assert_eq!(data.concat(), input.as_bytes());
+
+        // Chunks outlive the chunker, and can move between threads.
+        let last = chunks.last().unwrap().clone();
+        drop(chunks);
+        let last = thread::spawn(move || last.to_vec()).join().unwrap();
+        assert!(last.ends_with(b"999\n"));
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(MmapLineChunks::open(64, &path).unwrap().count(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+}
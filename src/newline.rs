@@ -0,0 +1,149 @@
+use std::io::{self, Read};
+
+/// Newline canonicalization transform.
+///
+/// Wraps a [`Read`]er and rewrites `\r\n` and lone `\r` line endings to `\n`
+/// as the data is read, so that chunks produced by
+/// [`LineChunks`](crate::LineChunks) always contain canonical line endings. A
+/// `\r\n` pair split across two reads is still collapsed into a single `\n`.
+///
+/// ```
+/// use linechunks::{LineChunks, NormalizeNewlines};
+///
+/// let input = &b"one\r\ntwo\rthree\n"[..];
+/// let chunks: Vec<_> = LineChunks::new(8192, NormalizeNewlines::new(input))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(chunks, vec![b"one\ntwo\nthree\n".to_vec()]);
+/// ```
+pub struct NormalizeNewlines<R> {
+    inner: R,
+    // Last byte seen was a `\r`, so a leading `\n` in the next read is the
+    // second half of a CRLF pair and must be dropped.
+    after_cr: bool,
+}
+
+impl<R: Read> NormalizeNewlines<R> {
+    pub fn new(inner: R) -> Self {
+        NormalizeNewlines {
+            inner,
+            after_cr: false,
+        }
+    }
+
+    /// Return the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for NormalizeNewlines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let len = self.inner.read(buf)?;
+            if len == 0 {
+                return Ok(0);
+            }
+
+            // Fast path: nothing to rewrite.
+            let first_cr = match memchr::memchr(b'\r', &buf[..len]) {
+                None if !self.after_cr || buf[0] != b'\n' => {
+                    self.after_cr = false;
+                    return Ok(len);
+                }
+                None => 0,
+                Some(first_cr) => first_cr,
+            };
+
+            // Compact in place. Output never outgrows input since each CR or
+            // CRLF turns into a single LF. Start from the beginning if it's
+            // the LF half of a CRLF split across reads, so it's dropped.
+            let start = if self.after_cr && buf[0] == b'\n' {
+                0
+            } else {
+                first_cr
+            };
+            let mut out = start;
+            for idx in start..len {
+                match buf[idx] {
+                    b'\r' => {
+                        buf[out] = b'\n';
+                        out += 1;
+                        self.after_cr = true;
+                    }
+                    b'\n' if self.after_cr => self.after_cr = false,
+                    byte => {
+                        buf[out] = byte;
+                        out += 1;
+                        self.after_cr = false;
+                    }
+                }
+            }
+
+            // A read consisting only of the LF half of a CRLF produces
+            // nothing; don't report that as EOF.
+            if out > 0 {
+                break Ok(out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader which returns at most one byte per read, to exercise split CRLF
+    /// pairs.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    /// Reader which returns the given pieces, one per read.
+    struct Pieces<'a>(&'a [&'a [u8]]);
+
+    impl Read for Pieces<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((piece, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            assert!(piece.len() <= buf.len());
+            buf[..piece.len()].copy_from_slice(piece);
+            self.0 = rest;
+            Ok(piece.len())
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut out = Vec::new();
+        NormalizeNewlines::new(&b"a\r\nb\rc\n\r\r\nd"[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"a\nb\nc\n\n\nd");
+    }
+
+    #[test]
+    fn test_normalize_split() {
+        let mut out = Vec::new();
+        NormalizeNewlines::new(Trickle(b"a\r\nb\r\n\r\nc\r"))
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"a\nb\n\nc\n");
+
+        // The LF half of a split CRLF is dropped even when there's another CR
+        // later in the same read.
+        let mut out = Vec::new();
+        NormalizeNewlines::new(Pieces(&[b"a\r", b"\nb\r\nc"]))
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"a\nb\nc");
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{self, Read};
+
+use crate::{Chunk, Delimiter, LineChunks};
+
+/// Iterate over chunks along with their starting byte offsets, from
+/// [`LineChunks::offsets`].
+///
+/// Offsets are positions in the input as read, so they stay correct when
+/// short chunks are coalesced, and skip over anything dropped by the error
+/// policy or [`LineChunks::skip_chunks`].
+pub struct Offsets<R, D> {
+    chunks: LineChunks<R, D>,
+}
+
+impl<R, D> Offsets<R, D> {
+    /// The underlying `LineChunks`, for its error report and other state.
+    pub fn get_ref(&self) -> &LineChunks<R, D> {
+        &self.chunks
+    }
+
+    pub fn into_inner(self) -> LineChunks<R, D> {
+        self.chunks
+    }
+}
+
+impl<R: Read, D: Delimiter> Iterator for Offsets<R, D> {
+    type Item = io::Result<(u64, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next_chunk(false)?;
+        Some(chunk.map(|chunk| (chunk.offset, chunk.data)))
+    }
+}
+
+/// Iterate over chunks with their index, offset and line count, from
+/// [`LineChunks::with_metadata`].
+pub struct WithMetadata<R, D> {
+    chunks: LineChunks<R, D>,
+}
+
+impl<R, D> WithMetadata<R, D> {
+    /// The underlying `LineChunks`, for its error report and other state.
+    pub fn get_ref(&self) -> &LineChunks<R, D> {
+        &self.chunks
+    }
+
+    pub fn into_inner(self) -> LineChunks<R, D> {
+        self.chunks
+    }
+}
+
+impl<R: Read, D: Delimiter> Iterator for WithMetadata<R, D> {
+    type Item = io::Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next_chunk(true)
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Yield each chunk with the byte offset of its start in the input.
+    pub fn offsets(self) -> Offsets<R, D> {
+        Offsets { chunks: self }
+    }
+
+    /// Yield each chunk as a [`Chunk`], with its index, offset and number
+    /// of lines. This is handy for sharding work by line ranges.
+    pub fn with_metadata(self) -> WithMetadata<R, D> {
+        WithMetadata { chunks: self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Chunk, ErrorPolicy, LineChunks};
+
+    #[test]
+    fn test_offsets() {
+        let input = &b"one\ntwo\nthree\nfour\nfive"[..];
+        let mut chunks = LineChunks::new(6, input);
+        chunks.min_chunk(8);
+
+        let offsets: Vec<_> = chunks.offsets().map(Result::unwrap).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                (0, b"one\ntwo\n".to_vec()),
+                (8, b"three\nfour\n".to_vec()),
+                (19, b"five".to_vec()),
+            ]
+        );
+
+        // A dropped over-long line leaves a gap.
+        let mut chunks = LineChunks::new(4, &b"ab\ncdefghijkl\nmn\n"[..]);
+        chunks.max_line(6);
+        chunks.min_chunk(1);
+        chunks.error_policy(ErrorPolicy::SkipAndCount);
+
+        let offsets: Vec<u64> = chunks.offsets().map(|res| res.unwrap().0).collect();
+        assert_eq!(offsets, [0, 14]);
+    }
+
+    #[test]
+    fn test_with_metadata() {
+        let input = &b"one\ntwo\nthree\nfour\nfive"[..];
+        let mut chunks = LineChunks::new(6, input);
+        chunks.min_chunk(8);
+
+        let chunks: Vec<_> = chunks.with_metadata().map(Result::unwrap).collect();
+        assert_eq!(
+            chunks[1],
+            Chunk {
+                index: 1,
+                offset: 8,
+                lines: 2,
+                data: b"three\nfour\n".to_vec(),
+            }
+        );
+        assert_eq!(chunks.iter().map(|chunk| chunk.lines).sum::<u64>(), 5);
+        assert_eq!(chunks[2].index, 2);
+
+        let lines: Vec<_> = chunks[1].lines_with_offsets().collect();
+        assert_eq!(lines, [(8, &b"three"[..]), (14, b"four")]);
+        assert_eq!(chunks[1].lines().count(), 2);
+        assert_eq!(chunks[2].as_str(), Ok("five"));
+        assert_eq!(chunks[0].clone().into_vec(), b"one\ntwo\n");
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    io::{self, Read},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use rayon::Yield;
+
+use crate::{Delimiter, LineChunks};
+
+type Done<T> = (usize, Result<io::Result<T>, Box<dyn Any + Send>>);
+
+/// Map chunks on the rayon pool, yielding the results in input order.
+/// Created by [`LineChunks::par_map_ordered`].
+///
+/// Chunks are read on the calling thread as results are taken, and at most
+/// [`max_in_flight`](Self::max_in_flight) chunks are being mapped or waiting
+/// to be yielded at once, so memory use is bounded however far ahead the
+/// workers get. The first error, from reading or from `f`, is yielded in its
+/// place and ends iteration. A panic in `f` is propagated to the caller.
+///
+/// This can be used from inside the rayon pool too, even one with a single
+/// thread: while waiting for a result there, the caller runs other pool work,
+/// including mapping its own chunks, rather than blocking.
+pub struct ParMapOrdered<R, D, F, T> {
+    chunks: LineChunks<R, D>,
+    f: Arc<F>,
+    max_in_flight: usize,
+    // Index of the next chunk to read, and of the next result to yield.
+    next_read: usize,
+    next_out: usize,
+    input_done: bool,
+    finished: bool,
+    pending: BTreeMap<usize, io::Result<T>>,
+    send: Sender<Done<T>>,
+    recv: Receiver<Done<T>>,
+}
+
+impl<R, D, F, T> ParMapOrdered<R, D, F, T> {
+    /// Limit the number of chunks read but not yet yielded. Defaults to twice
+    /// the number of threads in the rayon pool.
+    pub fn max_in_flight(&mut self, max: usize) {
+        self.max_in_flight = max.max(1);
+    }
+}
+
+impl<R, D, F, T> ParMapOrdered<R, D, F, T>
+where
+    R: Read,
+    D: Delimiter,
+    F: Fn(Vec<u8>) -> io::Result<T> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    /// Read chunks and start mapping them until we're at the limit.
+    fn fill(&mut self) {
+        while !self.input_done && self.next_read - self.next_out < self.max_in_flight {
+            let index = self.next_read;
+            self.next_read += 1;
+
+            match self.chunks.next() {
+                Some(Ok(chunk)) => {
+                    let f = self.f.clone();
+                    let send = self.send.clone();
+                    rayon::spawn(move || {
+                        let res = panic::catch_unwind(AssertUnwindSafe(|| f(chunk)));
+                        // The iterator may have been dropped.
+                        let _ = send.send((index, res));
+                    });
+                }
+                Some(Err(err)) => {
+                    self.pending.insert(index, Err(err));
+                    self.input_done = true;
+                }
+                None => {
+                    self.next_read -= 1;
+                    self.input_done = true;
+                }
+            }
+        }
+    }
+}
+
+impl<R, D, F, T> ParMapOrdered<R, D, F, T> {
+    /// Wait for the next result from a worker.
+    fn wait(&self) -> Done<T> {
+        // On a pool thread, blocking could leave nothing to run the jobs we
+        // spawned, so help run them instead.
+        while rayon::current_thread_index().is_some() {
+            if let Ok(done) = self.recv.try_recv() {
+                return done;
+            }
+            if rayon::yield_now() == Some(Yield::Idle) {
+                thread::yield_now();
+            }
+        }
+        self.recv.recv().expect("worker disappeared")
+    }
+}
+
+impl<R, D, F, T> Iterator for ParMapOrdered<R, D, F, T>
+where
+    R: Read,
+    D: Delimiter,
+    F: Fn(Vec<u8>) -> io::Result<T> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            self.fill();
+
+            if let Some(res) = self.pending.remove(&self.next_out) {
+                self.next_out += 1;
+                self.finished = res.is_err();
+                break Some(res);
+            }
+            if self.next_out == self.next_read {
+                self.finished = true;
+                break None;
+            }
+
+            let (index, res) = self.wait();
+            match res {
+                Ok(res) => {
+                    self.pending.insert(index, res);
+                }
+                Err(panic) => {
+                    self.finished = true;
+                    panic::resume_unwind(panic);
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Apply `f` to each chunk in parallel on the rayon pool, and yield the
+    /// results in input order. This is for transforms whose output has to
+    /// stay in order, such as rewriting a file.
+    ///
+    /// ```
+    /// let input = &b"a\nb\nc\n"[..];
+    /// let upper: Vec<u8> = linechunks::LineChunks::new(2, input)
+    ///     .par_map_ordered(|chunk| Ok(chunk.to_ascii_uppercase()))
+    ///     .collect::<std::io::Result<Vec<_>>>()?
+    ///     .concat();
+    /// assert_eq!(upper, b"A\nB\nC\n");
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn par_map_ordered<F, T>(self, f: F) -> ParMapOrdered<R, D, F, T>
+    where
+        F: Fn(Vec<u8>) -> io::Result<T> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let (send, recv) = mpsc::channel();
+        ParMapOrdered {
+            chunks: self,
+            f: Arc::new(f),
+            max_in_flight: rayon::current_num_threads() * 2,
+            next_read: 0,
+            next_out: 0,
+            input_done: false,
+            finished: false,
+            pending: BTreeMap::new(),
+            send,
+            recv,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn test_par_map_ordered() {
+        let input: String = (0..200).map(|i| format!("{i}\n")).collect();
+
+        // Chunks take varying times, so finish out of order.
+        let mut mapped = LineChunks::new(4, input.as_bytes()).par_map_ordered(|chunk| {
+            thread::sleep(Duration::from_micros(u64::from(chunk[0] % 7) * 50));
+            Ok(chunk)
+        });
+        mapped.max_in_flight(8);
+        let output: Vec<u8> = mapped.flat_map(Result::unwrap).collect();
+        assert_eq!(output, input.as_bytes());
+
+        let results: Vec<_> = LineChunks::new(2, &b"a\nb\nc\n"[..])
+            .par_map_ordered(|chunk| match &chunk[..] {
+                b"b\n" => Err(io::Error::other("bad chunk")),
+                _ => Ok(chunk),
+            })
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+
+        // From inside a single-threaded pool, which must not deadlock.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let output: Vec<u8> = pool.install(|| {
+            LineChunks::new(4, input.as_bytes())
+                .par_map_ordered(Ok)
+                .flat_map(Result::unwrap)
+                .collect()
+        });
+        assert_eq!(output, input.as_bytes());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    fs::File,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{split::split_points, LineChunks};
+
+/// Shard a file into independent [`LineChunks`], one per worker.
+///
+/// The file is divided into line-aligned byte ranges with
+/// [`split_points`](crate::split_points), and each shard opens the file
+/// separately and chunks only its own range. The shards can be sent to
+/// different threads, so reading is parallel as well as processing. Chunk
+/// offsets are positions in the whole file.
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let shards = linechunks::ParallelLineChunks::new(1 << 20, "data.txt", 8)?;
+/// std::thread::scope(|s| {
+///     for shard in shards.shards()? {
+///         s.spawn(move || shard.map(|chunk| chunk.map(|c| c.len())).sum::<std::io::Result<usize>>());
+///     }
+///     Ok(())
+/// })
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParallelLineChunks {
+    path: PathBuf,
+    chunksize: usize,
+    ranges: Vec<Range<u64>>,
+}
+
+impl ParallelLineChunks {
+    /// Plan `workers` shards of `path`, chunked with `chunksize` as for
+    /// [`LineChunks::new`]. There may be empty shards if lines are long
+    /// compared to the file.
+    pub fn new<P: AsRef<Path>>(chunksize: usize, path: P, workers: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        let points = split_points(&mut File::open(path)?, workers)?;
+
+        Ok(ParallelLineChunks {
+            path: path.to_path_buf(),
+            chunksize,
+            ranges: points.windows(2).map(|pair| pair[0]..pair[1]).collect(),
+        })
+    }
+
+    /// The byte range of each shard, in file order.
+    pub fn ranges(&self) -> &[Range<u64>] {
+        &self.ranges
+    }
+
+    /// Open shard `index`.
+    pub fn shard(&self, index: usize) -> io::Result<LineChunks<io::Take<File>>> {
+        let range = &self.ranges[index];
+        LineChunks::range(
+            self.chunksize,
+            File::open(&self.path)?,
+            range.start,
+            range.end,
+        )
+    }
+
+    /// Open all the shards, in file order.
+    pub fn shards(&self) -> io::Result<Vec<LineChunks<io::Take<File>>>> {
+        (0..self.ranges.len())
+            .map(|index| self.shard(index))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_shards() {
+        let path = env::temp_dir().join(format!("linechunks-shards-{}", process::id()));
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        fs::write(&path, &input).unwrap();
+
+        let sharded = ParallelLineChunks::new(256, &path, 4).unwrap();
+        assert_eq!(sharded.ranges().len(), 4);
+
+        let parts: Vec<Vec<u8>> = thread::scope(|s| {
+            let handles: Vec<_> = sharded
+                .shards()
+                .unwrap()
+                .into_iter()
+                .map(|shard| s.spawn(|| shard.flat_map(Result::unwrap).collect()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        fs::remove_file(&path).unwrap();
+
+        assert!(parts.iter().all(|part| part.ends_with(b"\n")));
+        assert_eq!(parts.concat(), input.as_bytes());
+    }
+}
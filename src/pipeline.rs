@@ -0,0 +1,219 @@
+use std::io::{self, Read, Write};
+
+use crate::{scoped::scoped_for_each, ChunkConfig, LineSplitParse, OrderedSink};
+
+/// A parallel line transform, from a reader through a parser to a writer.
+///
+/// This wires together the pieces of a typical pipeline: chunks are read
+/// ahead on their own thread with
+/// [`spawn_prefetch`](crate::LineChunks::spawn_prefetch), each line is parsed
+/// on a pool of scoped threads, and the output is reassembled in input order
+/// by an [`OrderedSink`]. Memory use is bounded by the prefetch depth and the
+/// sink's buffering.
+///
+/// The parser is called with each non-empty line, without its `\n`, and
+/// appends whatever it wants to output for that line to the buffer it's
+/// given.
+///
+/// ```
+/// use std::io::Write;
+/// use linechunks::Pipeline;
+///
+/// let input = std::io::Cursor::new(b"1\n2\n3\n".to_vec());
+/// let output = Pipeline::new(input)
+///     .parse(|line, out: &mut Vec<u8>| {
+///         let n: u32 = std::str::from_utf8(line).unwrap().parse().unwrap();
+///         writeln!(out, "{}", n * 2)
+///     })
+///     .sink(Vec::new())
+///     .run_parallel(4)?;
+/// assert_eq!(output, b"2\n4\n6\n");
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct Pipeline<R, P, W> {
+    read: R,
+    config: ChunkConfig,
+    prefetch: Option<usize>,
+    memory_limit: Option<usize>,
+    parser: P,
+    sink: W,
+}
+
+impl<R: Read + Send + 'static> Pipeline<R, (), io::Sink> {
+    /// Read from `read`, with chunking configured by
+    /// [`ChunkConfig::default`]. A parser must be given with
+    /// [`parse`](Self::parse), and output is discarded unless a
+    /// [`sink`](Self::sink) is given.
+    pub fn new(read: R) -> Self {
+        Pipeline {
+            read,
+            config: ChunkConfig::default(),
+            prefetch: None,
+            memory_limit: None,
+            parser: (),
+            sink: io::sink(),
+        }
+    }
+}
+
+impl<R, P, W> Pipeline<R, P, W> {
+    /// How to chunk the input.
+    pub fn chunk(mut self, config: ChunkConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// How many chunks to read ahead. Defaults to the number of threads.
+    pub fn prefetch(mut self, chunks: usize) -> Self {
+        self.prefetch = Some(chunks);
+        self
+    }
+
+    /// Limit the output buffered while waiting for earlier chunks. See
+    /// [`OrderedSink::memory_limit`].
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// The parser to call with each line.
+    pub fn parse<F>(self, parser: F) -> Pipeline<R, F, W>
+    where
+        F: Fn(&[u8], &mut Vec<u8>) -> io::Result<()> + Sync,
+    {
+        Pipeline {
+            read: self.read,
+            config: self.config,
+            prefetch: self.prefetch,
+            memory_limit: self.memory_limit,
+            parser,
+            sink: self.sink,
+        }
+    }
+
+    /// Where to write the output.
+    pub fn sink<S: Write>(self, sink: S) -> Pipeline<R, P, S> {
+        Pipeline {
+            read: self.read,
+            config: self.config,
+            prefetch: self.prefetch,
+            memory_limit: self.memory_limit,
+            parser: self.parser,
+            sink,
+        }
+    }
+}
+
+/// Aborts the sink if a chunk fails to parse or its parser panics, since
+/// nothing after it can be written.
+struct AbortGuard<'a, W: Write> {
+    sink: &'a OrderedSink<W>,
+    done: bool,
+}
+
+impl<W: Write> Drop for AbortGuard<'_, W> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.sink.abort();
+        }
+    }
+}
+
+impl<R, P, W> Pipeline<R, P, W>
+where
+    R: Read + Send + 'static,
+    P: Fn(&[u8], &mut Vec<u8>) -> io::Result<()> + Sync,
+    W: Write + Send,
+{
+    /// Run the pipeline with `nthreads` parser threads, returning the sink
+    /// once all the output has been written and flushed. The first error,
+    /// from reading, parsing or writing, stops the pipeline and is returned.
+    pub fn run_parallel(self, nthreads: usize) -> io::Result<W> {
+        let nthreads = nthreads.max(1);
+        let chunks = self
+            .config
+            .chunker(self.read)
+            .spawn_prefetch(self.prefetch.unwrap_or(nthreads));
+
+        let mut sink = OrderedSink::new(self.sink);
+        if let Some(limit) = self.memory_limit {
+            sink.memory_limit(limit);
+        }
+
+        let parser = &self.parser;
+        let indexed = (0..).zip(chunks).map(|(index, chunk)| Ok((index, chunk?)));
+        scoped_for_each(indexed, nthreads, |(index, chunk)| {
+            let mut guard = AbortGuard {
+                sink: &sink,
+                done: false,
+            };
+            let mut out = Vec::with_capacity(chunk.len());
+            LineSplitParse::new(chunk, |line: &[u8]| parser(line, &mut out))
+                .collect::<io::Result<()>>()?;
+
+            guard.done = true;
+            sink.write_chunk(index, out)
+        })?;
+
+        sink.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        let expected: String = (0..10_000)
+            .filter(|i| i % 2 == 0)
+            .map(|i| format!("{}\n", i * 3))
+            .collect();
+
+        let parse = |line: &[u8], out: &mut Vec<u8>| {
+            let n: u64 = std::str::from_utf8(line)
+                .ok()
+                .and_then(|line| line.parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad line"))?;
+            if n.is_multiple_of(2) {
+                writeln!(out, "{}", n * 3)?;
+            }
+            Ok(())
+        };
+        let config = ChunkConfig {
+            chunk_size: 256,
+            ..ChunkConfig::default()
+        };
+
+        let output = Pipeline::new(io::Cursor::new(input.into_bytes()))
+            .chunk(config.clone())
+            .parse(parse)
+            .sink(Vec::new())
+            .memory_limit(1024)
+            .run_parallel(4)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        let err = Pipeline::new(io::Cursor::new(b"1\nx\n3\n".to_vec()))
+            .chunk(config.clone())
+            .parse(parse)
+            .run_parallel(2)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A panicking parser doesn't leave the other workers waiting.
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        let res = std::panic::catch_unwind(|| {
+            Pipeline::new(io::Cursor::new(input.into_bytes()))
+                .chunk(config)
+                .parse(|line, _| {
+                    assert_ne!(line, b"100");
+                    Ok(())
+                })
+                .memory_limit(0)
+                .run_parallel(4)
+        });
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+use rayon::{
+    iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer},
+    prelude::*,
+};
+
+use crate::{
+    shutdown::Shutdown,
+    split::{plan_splits, split_ranges},
+};
+
+trait ReadSeek: Read + Seek + Send {}
+
+impl<R: Read + Seek + Send> ReadSeek for R {}
+
+type Opener = Arc<dyn Fn() -> io::Result<Box<dyn ReadSeek>> + Send + Sync>;
+
+/// A plan of line-aligned byte ranges for processing a file in parallel.
+///
+/// Planning only probes the file at each multiple of the chunk size to find
+/// the next line boundary, without reading the data in between, so it's cheap
+/// even for very large files. The plan can then be inspected, for example to
+/// estimate the cost of a run or the number of tasks, before it's executed.
+///
+/// Each thread reading chunks needs its own reader, so a plan is either of a
+/// file, which is opened again as needed, or of any `Read + Seek` source
+/// which can be opened again by a function, with
+/// [`from_opener`](Self::from_opener).
+#[derive(Clone)]
+pub struct Plan {
+    open: Opener,
+    ranges: Vec<Range<u64>>,
+}
+
+impl fmt::Debug for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plan")
+            .field("ranges", &self.ranges)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Plan {
+    /// Plan `path` in chunks of roughly `chunksize` bytes. Chunks are larger
+    /// if needed to end at a line boundary.
+    pub fn new<P: AsRef<Path>>(path: P, chunksize: usize) -> io::Result<Plan> {
+        let path = path.as_ref().to_path_buf();
+        let ranges = plan_splits(&path, chunksize as u64)?;
+
+        Ok(Plan {
+            open: Arc::new(move || Ok(Box::new(File::open(&path)?) as Box<dyn ReadSeek>)),
+            ranges,
+        })
+    }
+
+    /// Plan a source opened by `open`, in chunks of roughly `chunksize`
+    /// bytes. `open` is called once to plan, and again by each thread which
+    /// reads chunks, so each gets its own reader, which must read the same
+    /// data. For example, it can clone a `Cursor` over shared data, or open
+    /// an object in a store.
+    ///
+    /// ```
+    /// use std::{io::Cursor, sync::Arc};
+    ///
+    /// let data: Arc<[u8]> = Arc::from(&b"a\nb\nc\n"[..]);
+    /// let plan = linechunks::Plan::from_opener(move || Ok(Cursor::new(data.clone())), 2)?;
+    /// assert_eq!(plan.ranges(), [0..2, 2..4, 4..6]);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn from_opener<F, R>(open: F, chunksize: usize) -> io::Result<Plan>
+    where
+        F: Fn() -> io::Result<R> + Send + Sync + 'static,
+        R: Read + Seek + Send + 'static,
+    {
+        let mut read = open()?;
+        let len = read.seek(SeekFrom::End(0))?;
+        let ranges = split_ranges(&mut read, len, chunksize as u64)?;
+
+        Ok(Plan {
+            open: Arc::new(move || Ok(Box::new(open()?) as Box<dyn ReadSeek>)),
+            ranges,
+        })
+    }
+
+    /// The byte range of each chunk, in file order.
+    pub fn ranges(&self) -> &[Range<u64>] {
+        &self.ranges
+    }
+
+    /// Read and process each chunk of the plan in parallel.
+    ///
+    /// `f` is called with each chunk's byte range and its data. Chunks are
+    /// processed in no particular order. Returns the first error from reading
+    /// the file or from `f`. If `f` panics, no further chunks are read and the
+    /// panic is propagated.
+    pub fn execute<F>(&self, f: F) -> io::Result<()>
+    where
+        F: Fn(Range<u64>, Vec<u8>) -> io::Result<()> + Sync,
+    {
+        let shutdown = Shutdown::default();
+
+        self.ranges.par_iter().try_for_each(|range| {
+            if shutdown.is_stopped() {
+                return Ok(());
+            }
+
+            let mut file = (self.open)()?;
+            file.seek(SeekFrom::Start(range.start))?;
+            let mut chunk = Vec::with_capacity((range.end - range.start) as usize);
+            file.take(range.end - range.start).read_to_end(&mut chunk)?;
+
+            let _guard = shutdown.guard();
+            f(range.clone(), chunk)
+        })
+    }
+}
+
+/// Reads each chunk of a [`Plan`] as an indexed parallel iterator.
+///
+/// Unlike `par_bridge` over a [`LineChunks`](crate::LineChunks), chunks are
+/// read in parallel, and the iterator keeps its order, so it can be
+/// `collect`ed, `zip`ped or `enumerate`d. Each chunk is its data, or the
+/// error from reading it.
+///
+/// ```no_run
+/// use rayon::prelude::*;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let plan = linechunks::Plan::new("data.txt", 1 << 20)?;
+/// let lines: usize = plan
+///     .into_par_iter()
+///     .map(|chunk| chunk.map(|chunk| chunk.iter().filter(|&&c| c == b'\n').count()))
+///     .sum::<std::io::Result<usize>>()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ParChunks {
+    plan: Plan,
+}
+
+impl IntoParallelIterator for Plan {
+    type Iter = ParChunks;
+    type Item = io::Result<Vec<u8>>;
+
+    fn into_par_iter(self) -> ParChunks {
+        ParChunks { plan: self }
+    }
+}
+
+impl ParallelIterator for ParChunks {
+    type Item = io::Result<Vec<u8>>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.plan.ranges.len())
+    }
+}
+
+impl IndexedParallelIterator for ParChunks {
+    fn len(&self) -> usize {
+        self.plan.ranges.len()
+    }
+
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        callback.callback(RangeProducer {
+            open: &self.plan.open,
+            ranges: &self.plan.ranges,
+        })
+    }
+}
+
+/// Splits the ranges of a plan between threads.
+struct RangeProducer<'a> {
+    open: &'a Opener,
+    ranges: &'a [Range<u64>],
+}
+
+impl<'a> Producer for RangeProducer<'a> {
+    type Item = io::Result<Vec<u8>>;
+    type IntoIter = RangeReader<'a>;
+
+    fn into_iter(self) -> RangeReader<'a> {
+        RangeReader {
+            open: self.open,
+            ranges: self.ranges.iter(),
+            file: None,
+        }
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.ranges.split_at(index);
+        (
+            RangeProducer {
+                open: self.open,
+                ranges: left,
+            },
+            RangeProducer {
+                open: self.open,
+                ranges: right,
+            },
+        )
+    }
+}
+
+/// Reads a run of ranges on one thread, opening the source once.
+struct RangeReader<'a> {
+    open: &'a Opener,
+    ranges: std::slice::Iter<'a, Range<u64>>,
+    file: Option<Box<dyn ReadSeek>>,
+}
+
+impl RangeReader<'_> {
+    fn read(&mut self, range: &Range<u64>) -> io::Result<Vec<u8>> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert((self.open)()?),
+        };
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut chunk = vec![0; (range.end - range.start) as usize];
+        file.read_exact(&mut chunk)?;
+        Ok(chunk)
+    }
+}
+
+impl Iterator for RangeReader<'_> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next()?;
+        Some(self.read(range))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ranges.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for RangeReader<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next_back()?;
+        Some(self.read(range))
+    }
+}
+
+impl ExactSizeIterator for RangeReader<'_> {}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process, sync::Mutex};
+
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let path = env::temp_dir().join(format!("linechunks-plan-{}", process::id()));
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        fs::write(&path, &input).unwrap();
+
+        let plan = Plan::new(&path, 1000).unwrap();
+        assert!(plan.ranges().len() > 40);
+        assert_eq!(plan.ranges().first().unwrap().start, 0);
+        assert_eq!(plan.ranges().last().unwrap().end, input.len() as u64);
+
+        let chunks = Mutex::new(Vec::new());
+        plan.execute(|range, chunk| {
+            assert_eq!(chunk.len() as u64, range.end - range.start);
+            assert!(chunk.ends_with(b"\n"));
+            chunks.lock().unwrap().push((range.start, chunk));
+            Ok(())
+        })
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut chunks = chunks.into_inner().unwrap();
+        chunks.sort();
+        let data: Vec<u8> = chunks.into_iter().flat_map(|(_, chunk)| chunk).collect();
+        assert_eq!(data, input.as_bytes());
+    }
+
+    #[test]
+    fn test_par_chunks() {
+        let path = env::temp_dir().join(format!("linechunks-par-{}", process::id()));
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        fs::write(&path, &input).unwrap();
+
+        let plan = Plan::new(&path, 1000).unwrap();
+        let count = plan.ranges().len();
+        let chunks: Vec<(usize, Vec<u8>)> = plan
+            .into_par_iter()
+            .map(Result::unwrap)
+            .enumerate()
+            .rev()
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(chunks.len(), count);
+        assert_eq!(chunks[0].0, count - 1);
+        let data: Vec<u8> = chunks
+            .into_iter()
+            .rev()
+            .flat_map(|(_, chunk)| chunk)
+            .collect();
+        assert_eq!(data, input.as_bytes());
+
+        // Any seekable source which can be opened again.
+        let shared: Arc<[u8]> = Arc::from(input.as_bytes());
+        let plan = Plan::from_opener(move || Ok(io::Cursor::new(shared.clone())), 1000).unwrap();
+        assert_eq!(plan.ranges().len(), count);
+        let data: Vec<u8> = plan
+            .into_par_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(data, input.as_bytes());
+    }
+}
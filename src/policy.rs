@@ -0,0 +1,196 @@
+/// How errors are handled by the chunker, the line splitter and the helpers
+/// built on them.
+///
+/// Tolerated errors are recorded in an [`ErrorReport`] which can be inspected
+/// at the end of the run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Return the first error and stop.
+    #[default]
+    FailFast,
+    /// Skip over errors, only keeping count of them.
+    SkipAndCount,
+    /// Skip over errors, keeping up to `n` of them in the report. The error
+    /// after that is returned and stops iteration.
+    CollectUpTo(usize),
+}
+
+/// What [`LineChunks`](crate::LineChunks) does with a line longer than
+/// `max_line`. Memory use is bounded by `max_line` whichever is chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Return an error, subject to the [`ErrorPolicy`].
+    #[default]
+    Error,
+    /// Return the line so far as a chunk which doesn't end with a `\n`, and
+    /// carry on with the rest of the line in the next chunk.
+    SplitMidLine,
+    /// Drop the line.
+    SkipLine,
+    /// Keep the first `max_line` bytes of the line, with a `\n` added, and
+    /// drop the rest.
+    Truncate,
+}
+
+/// Errors tolerated under an [`ErrorPolicy`].
+#[derive(Debug)]
+pub struct ErrorReport<E> {
+    /// Number of errors skipped.
+    pub skipped: u64,
+    /// The errors collected with [`ErrorPolicy::CollectUpTo`].
+    pub errors: Vec<E>,
+}
+
+impl<E> Default for ErrorReport<E> {
+    fn default() -> Self {
+        ErrorReport {
+            skipped: 0,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl<E> ErrorReport<E> {
+    /// True if no errors were skipped.
+    pub fn is_empty(&self) -> bool {
+        self.skipped == 0
+    }
+}
+
+/// Applies an [`ErrorPolicy`], keeping the report.
+#[derive(Debug)]
+pub(crate) struct ErrorTracker<E> {
+    pub(crate) policy: ErrorPolicy,
+    pub(crate) report: ErrorReport<E>,
+}
+
+impl<E> ErrorTracker<E> {
+    pub(crate) fn new(policy: ErrorPolicy) -> Self {
+        ErrorTracker {
+            policy,
+            report: ErrorReport::default(),
+        }
+    }
+
+    /// Record an error. Returns it back if it isn't tolerated, in which case
+    /// the caller should return it and stop.
+    pub(crate) fn tolerate(&mut self, err: E) -> Option<E> {
+        match self.policy {
+            ErrorPolicy::FailFast => return Some(err),
+            ErrorPolicy::SkipAndCount => {}
+            ErrorPolicy::CollectUpTo(limit) => {
+                if self.report.errors.len() >= limit {
+                    return Some(err);
+                }
+                self.report.errors.push(err);
+            }
+        }
+        self.report.skipped += 1;
+        None
+    }
+}
+
+/// Apply an [`ErrorPolicy`] to an iterator of `Result`s.
+///
+/// Tolerated errors are dropped from the stream and recorded in the report.
+/// Once an error isn't tolerated it is returned, and iteration stops.
+///
+/// This is how the policy is applied to [`LineSplitParse`] with a fallible
+/// parser, or to any other stage which yields `Result`s.
+///
+/// [`LineSplitParse`]: crate::LineSplitParse
+pub struct WithPolicy<I, E> {
+    iter: I,
+    finished: bool,
+    tracker: ErrorTracker<E>,
+}
+
+impl<I, T, E> WithPolicy<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    pub fn new(iter: I, policy: ErrorPolicy) -> Self {
+        WithPolicy {
+            iter,
+            finished: false,
+            tracker: ErrorTracker::new(policy),
+        }
+    }
+
+    /// Errors tolerated so far.
+    pub fn report(&self) -> &ErrorReport<E> {
+        &self.tracker.report
+    }
+
+    /// Consume the iterator, returning the errors tolerated.
+    pub fn into_report(self) -> ErrorReport<E> {
+        self.tracker.report
+    }
+}
+
+impl<I, T, E> Iterator for WithPolicy<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.finished {
+                break None;
+            }
+
+            match self.iter.next()? {
+                Ok(item) => break Some(Ok(item)),
+                Err(err) => {
+                    if let Some(err) = self.tracker.tolerate(err) {
+                        self.finished = true;
+                        break Some(Err(err));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn items() -> impl Iterator<Item = Result<u32, u32>> {
+        (0..10).map(|i| if i % 3 == 0 { Err(i) } else { Ok(i) })
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let mut iter = WithPolicy::new(items(), ErrorPolicy::FailFast);
+
+        assert_eq!(iter.next(), Some(Err(0)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut iter = WithPolicy::new(items(), ErrorPolicy::SkipAndCount);
+
+        assert_eq!(
+            iter.by_ref().collect::<Vec<_>>(),
+            [1, 2, 4, 5, 7, 8].map(Ok)
+        );
+        assert_eq!(iter.report().skipped, 4);
+        assert!(iter.report().errors.is_empty());
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut iter = WithPolicy::new(items(), ErrorPolicy::CollectUpTo(2));
+
+        assert_eq!(
+            iter.by_ref().collect::<Vec<_>>(),
+            [Ok(1), Ok(2), Ok(4), Ok(5), Err(6)]
+        );
+        let report = iter.into_report();
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.errors, [0, 3]);
+    }
+}
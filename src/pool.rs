@@ -0,0 +1,191 @@
+use std::{
+    io::{self, Read},
+    mem,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+
+use crate::{Delimiter, LineChunks};
+
+/// A pool of chunk buffers, to reuse their allocations.
+///
+/// Chunks from [`LineChunks::pooled`] are [`PooledBuf`]s, which go back to
+/// the pool when dropped, and the chunker takes its next buffer from the
+/// pool. The pool can be cloned to share it, for example between several
+/// chunkers, and buffers can be dropped on any thread. At most `cap` free
+/// buffers are kept; more are freed as usual.
+#[derive(Clone, Debug)]
+pub struct BufferPool {
+    free: Arc<Mutex<Vec<Vec<u8>>>>,
+    cap: usize,
+}
+
+impl BufferPool {
+    pub fn new(cap: usize) -> Self {
+        BufferPool {
+            free: Arc::new(Mutex::new(Vec::with_capacity(cap))),
+            cap,
+        }
+    }
+
+    /// Number of free buffers in the pool.
+    pub fn available(&self) -> usize {
+        self.free
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+
+    /// Take an empty buffer with at least `capacity` bytes of capacity.
+    pub fn get(&self, capacity: usize) -> PooledBuf {
+        PooledBuf {
+            buf: self.take(capacity),
+            pool: Some(self.clone()),
+        }
+    }
+
+    pub(crate) fn take(&self, capacity: usize) -> Vec<u8> {
+        let buf = self
+            .free
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop();
+        match buf {
+            Some(mut buf) => {
+                buf.reserve(capacity);
+                buf
+            }
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    fn put(&self, mut buf: Vec<u8>) {
+        let mut free = self.free.lock().unwrap_or_else(|err| err.into_inner());
+        if free.len() < self.cap && buf.capacity() > 0 {
+            buf.clear();
+            free.push(buf);
+        }
+    }
+}
+
+/// A buffer which goes back to its [`BufferPool`] when dropped.
+#[derive(Debug)]
+pub struct PooledBuf {
+    buf: Vec<u8>,
+    pool: Option<BufferPool>,
+}
+
+impl PooledBuf {
+    /// Take the buffer out of the pool, to keep it.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        self.pool = None;
+        mem::take(&mut self.buf)
+    }
+}
+
+impl Deref for PooledBuf {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuf {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl AsRef<[u8]> for PooledBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.put(mem::take(&mut self.buf));
+        }
+    }
+}
+
+/// Chunks in pooled buffers, from [`LineChunks::pooled`].
+pub struct Pooled<R, D> {
+    chunks: LineChunks<R, D>,
+    pool: BufferPool,
+}
+
+impl<R, D> Pooled<R, D> {
+    /// The underlying `LineChunks`, for its error report and other state.
+    pub fn get_ref(&self) -> &LineChunks<R, D> {
+        &self.chunks
+    }
+
+    pub fn into_inner(self) -> LineChunks<R, D> {
+        self.chunks
+    }
+}
+
+impl<R: Read, D: Delimiter> Iterator for Pooled<R, D> {
+    type Item = io::Result<PooledBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        Some(chunk.map(|buf| PooledBuf {
+            buf,
+            pool: Some(self.pool.clone()),
+        }))
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Yield chunks in buffers from `pool`, which go back to it when they're
+    /// dropped, so a long run doesn't allocate a new buffer per chunk.
+    ///
+    /// ```
+    /// let pool = linechunks::BufferPool::new(4);
+    /// for chunk in linechunks::LineChunks::new(2, &b"a\nb\nc\n"[..]).pooled(pool.clone()) {
+    ///     let chunk = chunk?;
+    ///     // ...
+    /// }
+    /// assert!(pool.available() > 0);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn pooled(mut self, pool: BufferPool) -> Pooled<R, D> {
+        self.pool = Some(pool.clone());
+        Pooled { chunks: self, pool }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pooled() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let pool = BufferPool::new(2);
+
+        let mut chunks = LineChunks::new(64, input.as_bytes()).pooled(pool.clone());
+        let first = chunks.next().unwrap().unwrap();
+        let (ptr, mut len) = (first.as_ptr(), first.len());
+        drop(first);
+        assert_eq!(pool.available(), 1);
+
+        // A later chunk reuses the first one's buffer.
+        let mut reused = false;
+        for chunk in chunks {
+            let chunk = chunk.unwrap();
+            reused |= chunk.as_ptr() == ptr;
+            len += chunk.len();
+        }
+        assert!(reused);
+        assert_eq!(len, input.len());
+        assert!(pool.available() <= 2);
+
+        let kept = pool.get(10).into_vec();
+        assert!(kept.capacity() >= 10);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    splitter::{Splitter, Trim},
+    Chunk,
+};
+
+/// Where a line is, passed to the parser by [`LineSplitPos`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinePos {
+    /// Line number within the chunk, starting at 0. Skipped empty lines are
+    /// still counted.
+    pub line: u64,
+    /// Byte offset of the start of the line within the chunk.
+    pub offset: usize,
+    /// Byte offset of the start of the line in the input, if the chunk's
+    /// offset is known.
+    pub absolute: Option<u64>,
+}
+
+/// Like [`LineSplitParse`](crate::LineSplitParse), but the parser is also
+/// given the position of each line.
+///
+/// ```
+/// use linechunks::{LineChunks, LinePos, LineSplitPos};
+///
+/// let input = &b"one\n\nthree\n"[..];
+/// for chunk in LineChunks::new(8192, input).with_metadata() {
+///     let lines: Vec<_> = LineSplitPos::from_chunk(chunk?, |pos: LinePos, line: &[u8]| {
+///         (pos.line, pos.absolute, line.to_vec())
+///     })
+///     .collect();
+///     assert_eq!(lines[1], (2, Some(5), b"three".to_vec()));
+/// }
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct LineSplitPos<F> {
+    buf: Vec<u8>,
+    split: Splitter,
+    line: u64,
+    base: Option<u64>,
+    parser: F,
+}
+
+impl<F> LineSplitPos<F> {
+    pub fn new(buf: Vec<u8>, parser: F) -> Self {
+        LineSplitPos {
+            split: Splitter::new(buf.len()),
+            buf,
+            line: 0,
+            base: None,
+            parser,
+        }
+    }
+
+    /// Split a [`Chunk`] from [`LineChunks::with_metadata`](crate::LineChunks::with_metadata),
+    /// so absolute offsets are known.
+    pub fn from_chunk(chunk: Chunk, parser: F) -> Self {
+        let mut split = LineSplitPos::new(chunk.data, parser);
+        split.base = Some(chunk.offset);
+        split
+    }
+
+    /// Strip the `\r` from lines ending in `\r\n`, as for
+    /// [`LineSplitParse::trim_crlf`](crate::LineSplitParse::trim_crlf).
+    pub fn trim_crlf(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Crlf } else { Trim::None };
+    }
+
+    /// Strip ASCII whitespace from both ends of each line, as for
+    /// [`LineSplitParse::trim_whitespace`](crate::LineSplitParse::trim_whitespace).
+    /// The position is still that of the start of the line.
+    pub fn trim_whitespace(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Whitespace } else { Trim::None };
+    }
+
+    /// Pass lines to the parser with their terminator, if they have one.
+    pub fn keep_terminator(&mut self, keep: bool) {
+        self.split.keep_terminator = keep;
+    }
+
+    /// Pass empty lines to the parser, rather than skipping them.
+    pub fn keep_empty(&mut self, keep: bool) {
+        self.split.keep_empty = keep;
+    }
+
+    /// Split lines on `delim` rather than `\n`.
+    pub fn delimiter(&mut self, delim: u8) {
+        self.split.delim = delim;
+    }
+}
+
+impl<F, T> Iterator for LineSplitPos<F>
+where
+    F: FnMut(LinePos, &[u8]) -> T,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let span = self.split.next_span(&self.buf)?;
+            let line = self.line;
+            self.line += 1;
+
+            if let Some(range) = self.split.line(&self.buf, span) {
+                let pos = LinePos {
+                    line,
+                    offset: span.start,
+                    absolute: self.base.map(|base| base + span.start as u64),
+                };
+                break Some((self.parser)(pos, &self.buf[range]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let chunk = Chunk {
+            index: 3,
+            offset: 100,
+            lines: 4,
+            data: b"a\n\nbb\nccc".to_vec(),
+        };
+        let pos: Vec<_> = LineSplitPos::from_chunk(chunk, |pos, _: &[u8]| pos).collect();
+        assert_eq!(
+            pos,
+            vec![
+                LinePos {
+                    line: 0,
+                    offset: 0,
+                    absolute: Some(100)
+                },
+                LinePos {
+                    line: 2,
+                    offset: 3,
+                    absolute: Some(103)
+                },
+                LinePos {
+                    line: 3,
+                    offset: 6,
+                    absolute: Some(106)
+                },
+            ]
+        );
+
+        let mut split = LineSplitPos::new(b"x\0y".to_vec(), |pos: LinePos, _: &[u8]| pos.offset);
+        split.delimiter(0);
+        assert_eq!(split.collect::<Vec<_>>(), [0, 2]);
+
+        let mut split =
+            LineSplitPos::new(b" a\r\n\n b \r\n".to_vec(), |pos: LinePos, line: &[u8]| {
+                (pos.line, pos.offset, line.to_vec())
+            });
+        split.trim_whitespace(true);
+        split.keep_empty(true);
+        assert_eq!(
+            split.collect::<Vec<_>>(),
+            [
+                (0, 0, b"a".to_vec()),
+                (1, 4, b"".to_vec()),
+                (2, 5, b"b".to_vec())
+            ]
+        );
+
+        let mut split =
+            LineSplitPos::new(b"a\r\nb".to_vec(), |_: LinePos, line: &[u8]| line.to_vec());
+        split.keep_terminator(true);
+        assert_eq!(
+            split.collect::<Vec<_>>(),
+            [b"a\r\n".to_vec(), b"b".to_vec()]
+        );
+    }
+}
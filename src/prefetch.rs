@@ -0,0 +1,111 @@
+use std::{
+    io::{self, Read},
+    panic,
+    sync::mpsc::{self, Receiver},
+    thread::{self, JoinHandle},
+};
+
+use crate::{Delimiter, LineChunks};
+
+/// Chunks read ahead on a background thread, from
+/// [`LineChunks::spawn_prefetch`].
+///
+/// Reading and chunking the input overlaps with whatever the consumer does
+/// with each chunk. Up to `n` chunks are queued, so memory use is bounded at
+/// about `n + 2` chunks. Dropping this stops the thread once it next tries to
+/// hand over a chunk. A panic on the thread is propagated to the consumer.
+pub struct Prefetch<T = Vec<u8>> {
+    recv: Receiver<io::Result<T>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T> Iterator for Prefetch<T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.recv.recv() {
+            Ok(chunk) => Some(chunk),
+            Err(_) => {
+                if let Some(Err(panic)) = self.thread.take().map(JoinHandle::join) {
+                    panic::resume_unwind(panic);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Run `iter` on a new thread, staying up to `n` items ahead of the consumer.
+pub(crate) fn spawn_prefetch<I, T>(iter: I, n: usize) -> Prefetch<T>
+where
+    I: Iterator<Item = io::Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (send, recv) = mpsc::sync_channel(n.max(1));
+    let thread = thread::spawn(move || {
+        for item in iter {
+            if send.send(item).is_err() {
+                break;
+            }
+        }
+    });
+
+    Prefetch {
+        recv,
+        thread: Some(thread),
+    }
+}
+
+impl<R, D> LineChunks<R, D>
+where
+    R: Read + Send + 'static,
+    D: Delimiter + Send + 'static,
+{
+    /// Move reading and chunking onto a new thread, which stays up to `n`
+    /// chunks ahead of the consumer. An `n` of 0 is treated as 1.
+    ///
+    /// ```
+    /// let input = std::io::Cursor::new(b"a\nb\nc\n".to_vec());
+    /// let chunks = linechunks::LineChunks::new(2, input).spawn_prefetch(4);
+    /// assert_eq!(chunks.count(), 3);
+    /// ```
+    pub fn spawn_prefetch(self, n: usize) -> Prefetch {
+        spawn_prefetch(self, n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_prefetch() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let read = Arc::new(AtomicUsize::new(0));
+
+        let mut chunks = LineChunks::new(16, io::Cursor::new(input.clone()));
+        chunks.on_chunk({
+            let read = read.clone();
+            move |_| {
+                read.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let mut chunks = chunks.spawn_prefetch(2);
+
+        // The reader gets ahead, but not by more than the bound.
+        let first = chunks.next().unwrap().unwrap();
+        while read.load(Ordering::SeqCst) < 3 {
+            thread::yield_now();
+        }
+        thread::sleep(std::time::Duration::from_millis(10));
+        assert!(read.load(Ordering::SeqCst) <= 4);
+
+        let rest: Vec<u8> = chunks.flat_map(Result::unwrap).collect();
+        assert_eq!([first, rest].concat(), input.as_bytes());
+    }
+}
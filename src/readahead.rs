@@ -0,0 +1,15 @@
+use std::fs::File;
+
+/// Tell the kernel that `file` will be read sequentially from start to end,
+/// so it reads further ahead. This is only a hint, so failure is ignored.
+#[cfg(all(feature = "readahead", target_os = "linux"))]
+pub(crate) fn advise_sequential(file: &File) {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: the descriptor is open for as long as `file` is borrowed, and
+    // posix_fadvise doesn't change the file or its contents.
+    unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_SEQUENTIAL) };
+}
+
+#[cfg(not(all(feature = "readahead", target_os = "linux")))]
+pub(crate) fn advise_sequential(_file: &File) {}
@@ -0,0 +1,168 @@
+use std::io::{self, ErrorKind, Read};
+
+use memchr::memmem;
+
+use crate::LineChunksError;
+
+/// Chunk an input made of records separated by a multi-byte separator.
+///
+/// This is like [`LineChunks`](crate::LineChunks), but records end with a
+/// byte sequence such as `"\r\n\r\n"` or a sentinel string rather than a
+/// single byte. Every chunk ends with a complete separator, except at the end
+/// of the input, including separators split across reads. If separators can
+/// overlap themselves (like `"\r\n\r\n"` in `"\r\n\r\n\r\n"`), a chunk ends
+/// after the last possible match.
+///
+/// `min_chunk` and `max_record` default as they do for `LineChunks`. A
+/// record longer than `max_record` is a
+/// [`LineChunksError::MaxLineExceeded`] error, and ends iteration.
+pub struct RecordChunks<R> {
+    read: R,
+    separator: memmem::FinderRev<'static>,
+    chunksize: usize,
+    min_chunk: usize,
+    max_record: usize,
+    finished: bool,
+    accum: Vec<u8>,
+    // End of the last separator found in `accum`.
+    last_end: Option<usize>,
+    // Offset of the start of `accum` in the input.
+    offset: u64,
+}
+
+impl<R: Read> RecordChunks<R> {
+    /// Panics if `separator` is empty.
+    pub fn new(chunksize: usize, read: R, separator: &[u8]) -> Self {
+        assert!(!separator.is_empty(), "empty record separator");
+
+        RecordChunks {
+            read,
+            separator: memmem::FinderRev::new(separator).into_owned(),
+            chunksize,
+            min_chunk: chunksize * 3 / 4,
+            max_record: chunksize * 32,
+            finished: false,
+            accum: Vec::with_capacity(chunksize),
+            last_end: None,
+            offset: 0,
+        }
+    }
+
+    /// Minimum chunk size. See [`LineChunks::min_chunk`](crate::LineChunks::min_chunk).
+    pub fn min_chunk(&mut self, size: usize) {
+        self.min_chunk = size;
+    }
+
+    /// Max record length, including its separator.
+    pub fn max_record(&mut self, size: usize) {
+        self.max_record = size;
+    }
+
+    /// The record separator.
+    pub fn separator(&self) -> &[u8] {
+        self.separator.needle()
+    }
+
+    /// Read more input onto `accum`, returning how much was read.
+    fn fill(&mut self) -> io::Result<usize> {
+        let start = self.accum.len();
+        self.accum.resize(start + self.chunksize, 0);
+        let res = loop {
+            match self.read.read(&mut self.accum[start..]) {
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                res => break res,
+            }
+        };
+        self.accum.truncate(start + *res.as_ref().unwrap_or(&0));
+        res
+    }
+}
+
+impl<R: Read> Iterator for RecordChunks<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.finished {
+                break None;
+            }
+
+            let old_len = self.accum.len();
+            let len = match self.fill() {
+                Ok(len) => len,
+                Err(err) => {
+                    self.finished = true;
+                    break Some(Err(err));
+                }
+            };
+
+            if len == 0 {
+                self.finished = true;
+                let accum = std::mem::take(&mut self.accum);
+                break (!accum.is_empty()).then_some(Ok(accum));
+            }
+
+            // Look for a separator in the new data, or straddling the old and
+            // new, without overlapping the last one found.
+            let seplen = self.separator.needle().len();
+            let from = old_len
+                .saturating_sub(seplen - 1)
+                .max(self.last_end.unwrap_or(0));
+            if let Some(pos) = self.separator.rfind(&self.accum[from..]) {
+                self.last_end = Some(from + pos + seplen);
+            }
+
+            match self.last_end {
+                Some(end) if end >= self.min_chunk => {
+                    let rest = self.accum.split_off(end);
+                    self.last_end = None;
+                    self.offset += end as u64;
+                    break Some(Ok(std::mem::replace(&mut self.accum, rest)));
+                }
+                end if self.accum.len() - end.unwrap_or(0) > self.max_record => {
+                    self.finished = true;
+                    let partial = self.accum.split_off(end.unwrap_or(0));
+                    break Some(Err(LineChunksError::MaxLineExceeded {
+                        offset: self.offset + self.accum.len() as u64,
+                        accumulated: partial.len(),
+                        partial,
+                    }
+                    .into()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let input = &b"GET / HTTP/1.1\r\nHost: a\r\n\r\nGET /b HTTP/1.1\r\n\r\ntail"[..];
+
+        // Small reads, so separators are split between them.
+        for chunksize in 1..8 {
+            let mut chunks = RecordChunks::new(chunksize, input, b"\r\n\r\n");
+            chunks.min_chunk(1);
+
+            let chunks: Vec<_> = chunks.map(Result::unwrap).collect();
+            assert_eq!(chunks.concat(), input);
+            for chunk in &chunks[..chunks.len() - 1] {
+                assert!(chunk.ends_with(b"\r\n\r\n"), "{chunk:?}");
+            }
+            assert_eq!(chunks.last().unwrap(), b"tail");
+        }
+
+        let mut chunks = RecordChunks::new(4, &b"aaaaaaaaaaaa"[..], b"--");
+        chunks.max_record(8);
+        let err = chunks.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(matches!(
+            err.into(),
+            LineChunksError::MaxLineExceeded { offset: 0, .. }
+        ));
+    }
+}
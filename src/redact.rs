@@ -0,0 +1,172 @@
+use std::borrow::Cow;
+
+use memchr::memmem;
+
+enum Rule {
+    Literal {
+        finder: Box<memmem::Finder<'static>>,
+        with: Vec<u8>,
+    },
+    #[cfg(feature = "regex")]
+    Regex {
+        regex: regex::bytes::Regex,
+        with: Vec<u8>,
+    },
+}
+
+impl Rule {
+    /// Apply the rule to a line, returning it unchanged if it didn't match.
+    fn apply<'a>(&self, line: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Rule::Literal { finder, with } => {
+                let mut matches = finder.find_iter(line).peekable();
+                if matches.peek().is_none() {
+                    return Cow::Borrowed(line);
+                }
+
+                let mut out = Vec::with_capacity(line.len());
+                let mut lim = 0;
+                for start in matches {
+                    // find_iter doesn't yield overlapping matches.
+                    out.extend_from_slice(&line[lim..start]);
+                    out.extend_from_slice(with);
+                    lim = start + finder.needle().len();
+                }
+                out.extend_from_slice(&line[lim..]);
+                Cow::Owned(out)
+            }
+            #[cfg(feature = "regex")]
+            Rule::Regex { regex, with } => regex.replace_all(line, with.as_slice()),
+        }
+    }
+}
+
+/// Redact lines with an ordered set of substitution rules.
+///
+/// Each rule replaces every match in a line, and rules are applied in the
+/// order they were added, each to the output of the previous one. Rules only
+/// see one line at a time, without its `\n`, so line structure is preserved.
+/// With the `regex` feature, rules can also be regular expressions.
+///
+/// Redaction only needs `&self`, so chunks can be redacted in parallel, for
+/// example from a rayon `map` over `LineChunks::par_bridge()`.
+#[derive(Default)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Redactor::default()
+    }
+
+    /// Replace each occurrence of `pattern` with `with`. Panics if `pattern` is
+    /// empty or either contains a `\n`.
+    pub fn literal(&mut self, pattern: &[u8], with: &[u8]) {
+        assert!(!pattern.is_empty(), "empty redaction pattern");
+        assert!(
+            !pattern.contains(&b'\n') && !with.contains(&b'\n'),
+            "redaction rules can't span lines"
+        );
+
+        self.rules.push(Rule::Literal {
+            finder: Box::new(memmem::Finder::new(pattern).into_owned()),
+            with: with.to_vec(),
+        });
+    }
+
+    /// Replace each match of the regular expression `pattern` with `with`,
+    /// which can refer to capture groups as `$1` or `$name`. Panics if `with`
+    /// contains a `\n`.
+    #[cfg(feature = "regex")]
+    pub fn regex(&mut self, pattern: &str, with: &[u8]) -> Result<(), regex::Error> {
+        assert!(!with.contains(&b'\n'), "redaction rules can't span lines");
+
+        self.rules.push(Rule::Regex {
+            regex: regex::bytes::Regex::new(pattern)?,
+            with: with.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// Redact each line of `chunk`. The chunk is returned as it is if no rules
+    /// matched.
+    pub fn redact_chunk(&self, chunk: Vec<u8>) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut changed = false;
+
+        for line in chunk.split_inclusive(|&c| c == b'\n') {
+            let (line, eol) = match line.split_last() {
+                Some((b'\n', line)) => (line, &b"\n"[..]),
+                _ => (line, &b""[..]),
+            };
+
+            let mut redacted = Cow::Borrowed(line);
+            for rule in &self.rules {
+                if let Cow::Owned(new) = rule.apply(&redacted) {
+                    redacted = Cow::Owned(new);
+                }
+            }
+
+            if let Cow::Owned(_) = redacted {
+                if !changed {
+                    // Copy the lines before this one, which were unchanged.
+                    let done = line.as_ptr() as usize - chunk.as_ptr() as usize;
+                    out.reserve(chunk.len());
+                    out.extend_from_slice(&chunk[..done]);
+                    changed = true;
+                }
+            }
+            if changed {
+                out.extend_from_slice(&redacted);
+                out.extend_from_slice(eol);
+            }
+        }
+
+        if changed {
+            out
+        } else {
+            chunk
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_literal() {
+        let mut redactor = Redactor::new();
+        redactor.literal(b"secret", b"XXX");
+        redactor.literal(b"XXX-XXX", b"[pair]");
+
+        let chunk = b"plain\nsecret-secret here\nsecrets\n".to_vec();
+        assert_eq!(
+            redactor.redact_chunk(chunk),
+            b"plain\n[pair] here\nXXXs\n".to_vec()
+        );
+
+        let clean = b"nothing to see\n".to_vec();
+        let ptr = clean.as_ptr();
+        let clean = redactor.redact_chunk(clean);
+        assert_eq!(clean.as_ptr(), ptr);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let mut redactor = Redactor::new();
+        redactor
+            .regex(r"\b(\d{1,3})\.\d{1,3}\.\d{1,3}\.\d{1,3}\b", b"$1.x.x.x")
+            .unwrap();
+        redactor.literal(b"user=bob", b"user=?");
+
+        let chunk = b"10.1.2.3 user=bob GET /\n192.168.0.1 ok\nno match".to_vec();
+        assert_eq!(
+            redactor.redact_chunk(chunk),
+            b"10.x.x.x user=? GET /\n192.x.x.x ok\nno match".to_vec()
+        );
+        assert!(redactor.regex("(", b"").is_err());
+    }
+}
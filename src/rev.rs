@@ -0,0 +1,164 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::LineChunksError;
+
+/// Read a seekable input into line-aligned chunks, starting from the end.
+///
+/// Each chunk is in forward order internally, but the chunks are yielded last
+/// first, so reversing the order of the chunks and concatenating them gives
+/// back the input. Only as much of the input as the chunks taken is read, so
+/// tools like `tail` can find the last lines of a large file cheaply.
+///
+/// As with [`LineChunks`](crate::LineChunks), a line may grow a chunk past
+/// `chunksize`, up to a maximum line length of 32 times `chunksize`. A longer
+/// line is an error, with the offset of the part of it which was read and
+/// that part as the partial line, and ends iteration.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// // The last 2 lines, like `tail -n 2`
+/// let input = Cursor::new(b"one\ntwo\nthree\nfour\n".to_vec());
+/// let mut tail = Vec::new();
+/// for chunk in linechunks::RevLineChunks::new(8, input) {
+///     let chunk = chunk?;
+///     tail.splice(0..0, chunk.split(|&c| c == b'\n').filter(|l| !l.is_empty()).map(<[u8]>::to_vec));
+///     if tail.len() >= 2 {
+///         break;
+///     }
+/// }
+/// assert_eq!(tail[tail.len() - 2..], [b"three".to_vec(), b"four".to_vec()]);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct RevLineChunks<R> {
+    read: R,
+    chunksize: usize,
+    max_line: usize,
+    finished: bool,
+    // Offset of the start of the input not yet read, once known.
+    pos: Option<u64>,
+    // The start of the input after `pos` which hasn't been yielded yet.
+    tail: Vec<u8>,
+}
+
+impl<R: Read + Seek> RevLineChunks<R> {
+    /// The input is the whole of `read`, and nothing is read until the first
+    /// chunk is taken.
+    pub fn new(chunksize: usize, read: R) -> Self {
+        RevLineChunks {
+            read,
+            chunksize: chunksize.max(1),
+            max_line: chunksize.max(1) * 32,
+            finished: false,
+            pos: None,
+            tail: Vec::new(),
+        }
+    }
+
+    /// Set the maximum line length. This is at least the chunk size.
+    pub fn max_line(&mut self, size: usize) {
+        self.max_line = size.max(self.chunksize);
+    }
+
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    fn read_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut pos = match self.pos {
+            Some(pos) => pos,
+            None => self.read.seek(SeekFrom::End(0))?,
+        };
+
+        loop {
+            if pos == 0 {
+                self.finished = true;
+                let chunk = std::mem::take(&mut self.tail);
+                return Ok((!chunk.is_empty()).then_some(chunk));
+            }
+
+            // Read the block before `pos`, followed by what's left of the
+            // block after it.
+            let len = pos.min(self.chunksize as u64) as usize;
+            pos -= len as u64;
+            let mut data = vec![0; len];
+            self.read.seek(SeekFrom::Start(pos))?;
+            self.read.read_exact(&mut data)?;
+            data.append(&mut self.tail);
+            self.pos = Some(pos);
+
+            // The chunk starts after the first `\n`, which ends a line whose
+            // start may be in an earlier block. A `\n` right at the end of the
+            // input doesn't end a chunk.
+            let search = &data[..data.len() - 1];
+            match memchr::memchr(b'\n', search) {
+                Some(eol) if pos > 0 => {
+                    let chunk = data.split_off(eol + 1);
+                    self.tail = data;
+                    return Ok(Some(chunk));
+                }
+                _ => {
+                    if pos > 0 && data.len() > self.max_line {
+                        self.finished = true;
+                        return Err(LineChunksError::MaxLineExceeded {
+                            offset: pos,
+                            accumulated: data.len(),
+                            partial: data,
+                        }
+                        .into());
+                    }
+                    self.tail = data;
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for RevLineChunks<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_chunk() {
+            Ok(chunk) => chunk.map(Ok),
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_rev_chunks() {
+        let input: String = (0..1000)
+            .map(|i| format!("{}\n", "x".repeat(i % 23)))
+            .collect();
+
+        for size in [1, 7, 64, 10_000] {
+            let chunks: Vec<_> = RevLineChunks::new(size, Cursor::new(input.as_bytes()))
+                .map(Result::unwrap)
+                .collect();
+
+            assert!(chunks.iter().all(|chunk| chunk.ends_with(b"\n")));
+            let joined: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+            assert_eq!(joined, input.as_bytes());
+        }
+
+        let mut chunks = RevLineChunks::new(4, Cursor::new(&b"a\nmuch too long\nb"[..]));
+        chunks.max_line(6);
+        assert_eq!(chunks.next().unwrap().unwrap(), b"b");
+        let err = LineChunksError::from(chunks.next().unwrap().unwrap_err());
+        assert!(matches!(err, LineChunksError::MaxLineExceeded { .. }));
+        assert!(chunks.next().is_none());
+    }
+}
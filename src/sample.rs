@@ -0,0 +1,150 @@
+use crate::splitter;
+
+/// How [`Sample`] picks lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sampling {
+    /// Keep every Nth line, starting with the first.
+    EveryNth(u64),
+    /// Keep each line with probability `p`.
+    Probability { p: f64, seed: u64 },
+}
+
+impl Sampling {
+    /// Return whether the line with 0-based number `line` is in the sample.
+    ///
+    /// This only depends on the line number (and seed), so the decision is
+    /// deterministic no matter what order or which thread lines are
+    /// processed in.
+    pub fn keep(&self, line: u64) -> bool {
+        match *self {
+            Sampling::EveryNth(n) => line.is_multiple_of(n),
+            Sampling::Probability { p, seed } => {
+                // Compare against `p` scaled to the u64 range. Float to int
+                // conversion saturates, so p >= 1.0 keeps everything.
+                let threshold = (p * u64::MAX as f64) as u64;
+                p >= 1.0 || mix(seed, line) < threshold
+            }
+        }
+    }
+}
+
+/// SplitMix64 finalizer over the seed and line number, giving a well
+/// distributed pseudo-random value for each line without any sequential RNG
+/// state.
+pub(crate) const fn mix(seed: u64, line: u64) -> u64 {
+    let mut z = seed.wrapping_add(line.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Line sampling adaptor for an iterator of line-aligned chunks.
+///
+/// Each chunk is filtered down to the lines selected by the [`Sampling`]
+/// rule. Chunks with no selected lines are dropped entirely, and errors are
+/// passed through. Lines are numbered from the start of the stream (including
+/// empty lines), so the sample is the same however the output chunks are
+/// subsequently processed.
+pub struct Sample<I> {
+    chunks: I,
+    sampling: Sampling,
+    line: u64,
+}
+
+impl<I, E> Sample<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    /// Panics if `sampling` is `EveryNth(0)`.
+    pub fn new(chunks: I, sampling: Sampling) -> Self {
+        assert!(
+            sampling != Sampling::EveryNth(0),
+            "can't sample every 0th line"
+        );
+
+        Sample {
+            chunks,
+            sampling,
+            line: 0,
+        }
+    }
+
+    fn filter(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut keep = Vec::new();
+
+        for span in splitter::spans(chunk) {
+            if self.sampling.keep(self.line) {
+                keep.extend_from_slice(&chunk[span.start..span.next]);
+            }
+            self.line += 1;
+        }
+
+        keep
+    }
+}
+
+impl<I, E> Iterator for Sample<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    type Item = Result<Vec<u8>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let chunk = match self.chunks.next()? {
+                Ok(chunk) => chunk,
+                Err(err) => break Some(Err(err)),
+            };
+
+            let keep = self.filter(&chunk);
+            if !keep.is_empty() {
+                break Some(Ok(keep));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    fn numbers() -> String {
+        (0..10000).map(|i| format!("{i}\n")).collect()
+    }
+
+    #[test]
+    fn test_every_nth() {
+        let input = numbers();
+        let sample = Sample::new(
+            LineChunks::new(128, input.as_bytes()),
+            Sampling::EveryNth(1000),
+        );
+
+        let data: Vec<u8> = sample.flat_map(Result::unwrap).collect();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            (0..10)
+                .map(|i| format!("{}\n", i * 1000))
+                .collect::<String>()
+        );
+    }
+
+    #[test]
+    fn test_probability() {
+        let input = numbers();
+        let sampling = Sampling::Probability { p: 0.1, seed: 42 };
+        let run = |chunksize| {
+            Sample::new(LineChunks::new(chunksize, input.as_bytes()), sampling)
+                .flat_map(Result::unwrap)
+                .collect::<Vec<u8>>()
+        };
+
+        // Same sample regardless of chunking
+        let sample = run(128);
+        assert_eq!(sample, run(4096));
+
+        let lines = sample.iter().filter(|&&c| c == b'\n').count();
+        assert!((800..1200).contains(&lines), "lines {lines}");
+    }
+}
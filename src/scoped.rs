@@ -0,0 +1,137 @@
+use std::{
+    io::{self, Read},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::{shutdown::Shutdown, Delimiter, LineChunks};
+
+/// Run `f` on each item on `nthreads` scoped threads. Items are read on the
+/// calling thread and handed out in order. The first error from the items or
+/// `f` stops the work and is returned.
+pub(crate) fn scoped_for_each<I, T, F>(items: I, nthreads: usize, f: F) -> io::Result<()>
+where
+    I: Iterator<Item = io::Result<T>>,
+    T: Send,
+    F: Fn(T) -> io::Result<()> + Sync,
+{
+    let nthreads = nthreads.max(1);
+    let shutdown = Shutdown::default();
+    let error = Mutex::new(None);
+    let fail = |err| {
+        shutdown.stop();
+        error
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get_or_insert(err);
+    };
+
+    // The receiver is dropped once all the workers have gone, so the reader
+    // can't block on a full channel.
+    let (send, recv) = mpsc::sync_channel::<T>(nthreads);
+    let recv = Arc::new(Mutex::new(recv));
+
+    thread::scope(|s| {
+        for _ in 0..nthreads {
+            let recv = recv.clone();
+            let (shutdown, f, fail) = (&shutdown, &f, &fail);
+            s.spawn(move || loop {
+                let item = recv.lock().unwrap_or_else(|err| err.into_inner()).recv();
+                let Ok(item) = item else {
+                    break;
+                };
+                if shutdown.is_stopped() {
+                    break;
+                }
+
+                let _guard = shutdown.guard();
+                if let Err(err) = f(item) {
+                    fail(err);
+                }
+            });
+        }
+        drop(recv);
+
+        for item in shutdown.source(items) {
+            match item {
+                Ok(item) => {
+                    if send.send(item).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    fail(err);
+                    break;
+                }
+            }
+        }
+        drop(send);
+    });
+
+    match error.into_inner().unwrap_or_else(|err| err.into_inner()) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Process chunks concurrently on `nthreads` scoped threads, without
+    /// needing rayon. Since the threads are scoped, `f` can borrow from the
+    /// caller, for example to accumulate into a `Mutex` or atomic.
+    ///
+    /// Chunks are read on the calling thread and handed to the workers over a
+    /// channel, in no particular order, with up to `nthreads` queued. The
+    /// first error, from reading or from `f`, stops the work and is returned.
+    /// If `f` panics, no more chunks are read and the panic is propagated. An
+    /// `nthreads` of 0 is treated as 1.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let lines = AtomicUsize::new(0);
+    /// linechunks::LineChunks::new(4, &b"a\nb\nc\n"[..]).for_each_chunk_parallel(2, |chunk| {
+    ///     lines.fetch_add(chunk.iter().filter(|&&c| c == b'\n').count(), Ordering::Relaxed);
+    ///     Ok(())
+    /// })?;
+    /// assert_eq!(lines.into_inner(), 3);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn for_each_chunk_parallel<F>(self, nthreads: usize, f: F) -> io::Result<()>
+    where
+        F: Fn(Vec<u8>) -> io::Result<()> + Sync,
+    {
+        scoped_for_each(self, nthreads, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_for_each_chunk_parallel() {
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+
+        let total = AtomicUsize::new(0);
+        LineChunks::new(64, input.as_bytes())
+            .for_each_chunk_parallel(4, |chunk| {
+                total.fetch_add(chunk.len(), Ordering::Relaxed);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total.into_inner(), input.len());
+
+        let seen = AtomicUsize::new(0);
+        let res = LineChunks::new(64, input.as_bytes()).for_each_chunk_parallel(0, |chunk| {
+            seen.fetch_add(1, Ordering::Relaxed);
+            if chunk.windows(5).any(|line| line == b"\n100\n") {
+                return Err(io::Error::other("bad chunk"));
+            }
+            Ok(())
+        });
+        assert_eq!(res.unwrap_err().to_string(), "bad chunk");
+        assert!(seen.into_inner() < 100);
+    }
+}
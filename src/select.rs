@@ -0,0 +1,260 @@
+use std::{
+    io::{Read, Seek},
+    ops::Range,
+};
+
+use crate::{splitter, LineChunks, LineIndex};
+
+/// Select lines by number from an iterator of line-aligned chunks, like
+/// `sed -n 'a,bp'`.
+///
+/// Takes any number of 0-based, half-open line number ranges. Each output
+/// chunk holds the selected lines from one input chunk, in input order (lines
+/// selected by overlapping ranges are only output once). Chunks are only
+/// scanned to count their lines until a range is reached, and whole chunks
+/// inside a range are passed through without copying. Once the last range is
+/// complete no more input is read.
+///
+/// Line numbers aren't known without reading the input, so the chunks before
+/// and between ranges still need to be read, unless there's a [`LineIndex`]
+/// for a seekable input: then [`indexed`](SelectLines::indexed) seeks
+/// straight to the start of each range. If a prefix can be skipped by
+/// approximate position rather than line number, use
+/// [`LineChunks::skip_chunks`] first.
+pub struct SelectLines<I: Iterator> {
+    chunks: I,
+    ranges: Vec<Range<u64>>,
+    cur: usize,
+    line: u64,
+    // The byte offset of each range's first line, and how to seek the chunks
+    // there, returning an error to yield if it fails.
+    starts: Vec<u64>,
+    seek: Option<SeekFn<I>>,
+}
+
+type SeekFn<I> = fn(&mut I, u64) -> Option<<I as Iterator>::Item>;
+
+impl<I, E> SelectLines<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    pub fn new(chunks: I, ranges: impl IntoIterator<Item = Range<u64>>) -> Self {
+        let mut sorted: Vec<_> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+        sorted.sort_by_key(|r| r.start);
+
+        // Merge overlapping or adjacent ranges
+        let mut ranges: Vec<Range<u64>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+
+        SelectLines {
+            chunks,
+            ranges,
+            cur: 0,
+            line: 0,
+            starts: Vec::new(),
+            seek: None,
+        }
+    }
+}
+
+impl<R: Read + Seek> SelectLines<LineChunks<R>> {
+    /// Select lines from a seekable input using its `index`, seeking past
+    /// the lines which aren't selected rather than reading them. `chunks`
+    /// must start at the beginning of the indexed input. Like the index, this
+    /// is only for lines ending in `\n`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use linechunks::{LineChunks, LineIndex, SelectLines};
+    ///
+    /// let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+    /// let index = LineIndex::build(input.as_bytes())?;
+    /// let chunks = LineChunks::new(64, Cursor::new(input));
+    /// let lines: Vec<u8> = SelectLines::indexed(chunks, &index, [500..502])
+    ///     .collect::<Result<Vec<_>, _>>()?
+    ///     .concat();
+    /// assert_eq!(lines, b"500\n501\n");
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn indexed(
+        chunks: LineChunks<R>,
+        index: &LineIndex,
+        ranges: impl IntoIterator<Item = Range<u64>>,
+    ) -> Self {
+        let mut select = SelectLines::new(chunks, ranges);
+        // A range starting past the end of the index starts at the end of
+        // the input.
+        select.starts = select
+            .ranges
+            .iter()
+            .map(|range| {
+                let line = usize::try_from(range.start).unwrap_or(usize::MAX);
+                index.line(line).map_or(index.file_len(), |line| line.start)
+            })
+            .collect();
+        select.seek = Some(|chunks, offset| chunks.seek_to(offset).err().map(Err));
+        select
+    }
+}
+
+impl<I, E> Iterator for SelectLines<I>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+{
+    type Item = Result<Vec<u8>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Stop reading once we're past the last range
+            let range = self.ranges.get(self.cur)?.clone();
+
+            if let Some(seek) = self.seek.filter(|_| self.line < range.start) {
+                if let Some(err) = seek(&mut self.chunks, self.starts[self.cur]) {
+                    break Some(err);
+                }
+                self.line = range.start;
+            }
+
+            let chunk = match self.chunks.next()? {
+                Ok(chunk) => chunk,
+                Err(err) => break Some(Err(err)),
+            };
+
+            let start = self.line;
+            let mut lines = memchr::memchr_iter(b'\n', &chunk).count() as u64;
+            if chunk.last().is_some_and(|&c| c != b'\n') {
+                lines += 1;
+            }
+            let end = start + lines;
+            self.line = end;
+
+            if range.start >= end {
+                continue;
+            }
+
+            if range.start <= start && range.end >= end {
+                // Whole chunk selected
+                if range.end == end {
+                    self.cur += 1;
+                }
+                break Some(Ok(chunk));
+            }
+
+            let mut out = Vec::new();
+
+            for (line, span) in (start..).zip(splitter::spans(&chunk)) {
+                while self.ranges.get(self.cur).is_some_and(|r| line >= r.end) {
+                    self.cur += 1;
+                }
+                let Some(range) = self.ranges.get(self.cur) else {
+                    break;
+                };
+                if line >= range.start {
+                    out.extend_from_slice(&chunk[span.start..span.next]);
+                }
+            }
+
+            // Retire ranges which ended with this chunk
+            while self.ranges.get(self.cur).is_some_and(|r| r.end <= end) {
+                self.cur += 1;
+            }
+
+            if !out.is_empty() {
+                break Some(Ok(out));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        io::{self, Cursor, SeekFrom},
+        rc::Rc,
+    };
+
+    use super::*;
+
+    fn select(chunksize: usize, ranges: Vec<Range<u64>>) -> String {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let select = SelectLines::new(LineChunks::new(chunksize, input.as_bytes()), ranges);
+
+        String::from_utf8(select.flat_map(Result::unwrap).collect()).unwrap()
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_select() {
+        for chunksize in [4, 16, 100, 8192] {
+            assert_eq!(select(chunksize, vec![10..13]), "10\n11\n12\n");
+            assert_eq!(
+                select(chunksize, vec![998..2000, 5..7, 0..1, 6..8]),
+                "0\n5\n6\n7\n998\n999\n"
+            );
+            assert_eq!(select(chunksize, vec![5..5, 2000..3000]), "");
+        }
+    }
+
+    /// Counts the bytes read through it.
+    struct Counting<R>(R, Rc<Cell<usize>>);
+
+    impl<R: Read> Read for Counting<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.read(buf)?;
+            self.1.set(self.1.get() + len);
+            Ok(len)
+        }
+    }
+
+    impl<R: Seek> Seek for Counting<R> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.0.seek(pos)
+        }
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_indexed() {
+        let input: String = (0..100_000).map(|i| format!("{i}\n")).collect();
+        let index = LineIndex::build(input.as_bytes()).unwrap();
+
+        for ranges in [
+            vec![10..13],
+            vec![0..2, 50_000..50_003, 99_998..200_000],
+            vec![5..8, 6..10, 70_000..70_001],
+            vec![200_000..200_001],
+        ] {
+            let expected: Vec<u8> =
+                SelectLines::new(LineChunks::new(64, input.as_bytes()), ranges.clone())
+                    .flat_map(Result::unwrap)
+                    .collect();
+
+            let read = Rc::new(Cell::new(0));
+            let chunks = LineChunks::new(64, Counting(Cursor::new(&input), read.clone()));
+            let got: Vec<u8> = SelectLines::indexed(chunks, &index, ranges.clone())
+                .flat_map(Result::unwrap)
+                .collect();
+            assert_eq!(got, expected, "{ranges:?}");
+            // Only the chunks around each range were read.
+            assert!(read.get() < 1000, "{ranges:?} read {}", read.get());
+        }
+    }
+
+    #[test]
+    fn test_stop_early() {
+        let mut chunks = LineChunks::new(8, &b"a\nb\nc\nd\ne\nf\ng\nh\n"[..]);
+        let selected: Vec<_> = SelectLines::new(chunks.by_ref(), Some(1..2))
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(selected, vec![b"b\n".to_vec()]);
+        // The rest of the input wasn't consumed
+        assert!(chunks.next().is_some());
+    }
+}
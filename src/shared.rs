@@ -0,0 +1,76 @@
+use std::io::{self, Read};
+
+use bytes::Bytes;
+
+use crate::{Delimiter, LineChunks};
+
+/// Chunks as [`Bytes`], from [`LineChunks::bytes_chunks`].
+///
+/// A `Bytes` can be cloned and sliced without copying, and sent to other
+/// threads or tasks, such as a hyper body. Converting each chunk doesn't copy
+/// it either. To split a chunk into lines which are slices of it, use
+/// [`LineSplitParse`](crate::LineSplitParse) with [`Bytes::slice_ref`]:
+///
+/// ```
+/// use bytes::Bytes;
+/// use linechunks::{LineChunks, LineSplitParse};
+///
+/// for chunk in LineChunks::new(8, &b"one\ntwo\n"[..]).bytes_chunks() {
+///     let chunk = chunk?;
+///     let lines: Vec<Bytes> = LineSplitParse::new(chunk.clone(), |line: &[u8]| chunk.slice_ref(line)).collect();
+///     assert_eq!(lines, ["one", "two"]);
+/// }
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub struct BytesChunks<R, D> {
+    chunks: LineChunks<R, D>,
+}
+
+impl<R, D> BytesChunks<R, D> {
+    /// The underlying `LineChunks`, for its error report and other state.
+    pub fn get_ref(&self) -> &LineChunks<R, D> {
+        &self.chunks
+    }
+
+    pub fn into_inner(self) -> LineChunks<R, D> {
+        self.chunks
+    }
+}
+
+impl<R: Read, D: Delimiter> Iterator for BytesChunks<R, D> {
+    type Item = io::Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.chunks.next()?.map(Bytes::from))
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Yield chunks as [`Bytes`], for sharing them cheaply.
+    pub fn bytes_chunks(self) -> BytesChunks<R, D> {
+        BytesChunks { chunks: self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineSplitParse;
+
+    #[test]
+    fn test_bytes_chunks() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let mut lines = Vec::new();
+
+        for chunk in LineChunks::new(64, input.as_bytes()).bytes_chunks() {
+            let chunk = chunk.unwrap();
+            let mut split = LineSplitParse::new(chunk.clone(), |line: &[u8]| chunk.slice_ref(line));
+            split.keep_terminator(true);
+            lines.extend(split);
+        }
+
+        assert_eq!(lines.len(), 1000);
+        assert_eq!(lines[999], "999\n");
+        assert_eq!(lines.concat(), input.as_bytes());
+    }
+}
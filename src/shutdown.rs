@@ -0,0 +1,53 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+/// Stops a parallel pipeline when one of its workers panics.
+///
+/// A panic in a user closure running on one worker would otherwise only be
+/// propagated once every other worker had finished, which means reading the
+/// rest of the input first (or never, if it's unbounded). Instead, user code
+/// runs under a [`PanicGuard`], and the input is wrapped with
+/// [`Shutdown::source`] so it stops producing as soon as a panic starts
+/// unwinding. Buffers already handed out are dropped by the unwind, and the
+/// panic is then propagated by the thread pool as usual.
+#[derive(Default)]
+pub(crate) struct Shutdown {
+    stop: AtomicBool,
+}
+
+/// Stops the pipeline if it's dropped while panicking.
+pub(crate) struct PanicGuard<'a>(&'a Shutdown);
+
+impl Drop for PanicGuard<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Shutdown {
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Stop the pipeline without a panic, such as after an error.
+    pub(crate) fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Hold this while running user code.
+    pub(crate) fn guard(&self) -> PanicGuard<'_> {
+        PanicGuard(self)
+    }
+
+    /// Wrap the pipeline's input so that it ends once we've stopped.
+    pub(crate) fn source<'a, I>(&'a self, iter: I) -> impl Iterator<Item = I::Item> + 'a
+    where
+        I: Iterator + 'a,
+    {
+        iter.take_while(move |_| !self.is_stopped())
+    }
+}
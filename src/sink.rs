@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind, Write},
+    sync::{Condvar, Mutex, MutexGuard},
+};
+
+/// Write chunks processed in parallel to a writer in input order.
+///
+/// Workers call [`write_chunk`](Self::write_chunk) with each chunk's index,
+/// such as [`Chunk::index`](crate::Chunk::index), and its output, in
+/// whatever order they finish. The chunk due next is written straight away,
+/// along with any buffered chunks which follow it. Others are buffered, up
+/// to the [`memory_limit`](Self::memory_limit); beyond it, workers wait
+/// until their chunk is due or there's room. So the worker with the chunk
+/// due next must never be held up by the waiting ones, which is the case if
+/// chunks are handed out to workers in order.
+///
+/// If writing fails, that write and all later ones return an error. A worker
+/// which fails without writing its chunk should [`abort`](Self::abort) the
+/// sink, so others don't wait for that chunk forever.
+pub struct OrderedSink<W> {
+    state: Mutex<State<W>>,
+    ready: Condvar,
+    limit: usize,
+}
+
+struct State<W> {
+    write: W,
+    next: u64,
+    pending: BTreeMap<u64, Vec<u8>>,
+    buffered: usize,
+    failed: bool,
+}
+
+impl<W: Write> OrderedSink<W> {
+    /// Write to `write`, starting with chunk 0.
+    pub fn new(write: W) -> Self {
+        OrderedSink {
+            state: Mutex::new(State {
+                write,
+                next: 0,
+                pending: BTreeMap::new(),
+                buffered: 0,
+                failed: false,
+            }),
+            ready: Condvar::new(),
+            limit: 64 << 20,
+        }
+    }
+
+    /// Limit the bytes of out-of-order chunks buffered. Defaults to 64MB. A
+    /// single chunk larger than the limit is still buffered if it's the only
+    /// one.
+    pub fn memory_limit(&mut self, bytes: usize) {
+        self.limit = bytes;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<W>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Write the output for chunk `index`, or buffer it until the chunks
+    /// before it have been written. Each index must be written once.
+    pub fn write_chunk(&self, index: u64, chunk: Vec<u8>) -> io::Result<()> {
+        let mut state = self.lock();
+
+        loop {
+            if state.failed {
+                return Err(io::Error::other("output stopped by an earlier error"));
+            }
+            if index < state.next || state.pending.contains_key(&index) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("chunk {index} written twice"),
+                ));
+            }
+            if index == state.next
+                || state.pending.is_empty()
+                || state.buffered + chunk.len() <= self.limit
+            {
+                break;
+            }
+            state = self
+                .ready
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+
+        if index != state.next {
+            state.buffered += chunk.len();
+            state.pending.insert(index, chunk);
+            return Ok(());
+        }
+
+        let mut res = state.write.write_all(&chunk);
+        state.next += 1;
+        while res.is_ok() {
+            let next = state.next;
+            let Some(chunk) = state.pending.remove(&next) else {
+                break;
+            };
+            state.buffered -= chunk.len();
+            state.next += 1;
+            res = state.write.write_all(&chunk);
+        }
+
+        state.failed = res.is_err();
+        self.ready.notify_all();
+        res
+    }
+
+    /// Stop writing. Workers waiting to write, and any later writes, return
+    /// an error.
+    pub fn abort(&self) {
+        self.lock().failed = true;
+        self.ready.notify_all();
+    }
+
+    /// Flush and return the writer. It's an error if any chunk before the
+    /// last one written is missing.
+    pub fn finish(self) -> io::Result<W> {
+        let mut state = self
+            .state
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(&index) = state.pending.keys().next() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("chunk {} missing before chunk {index}", state.next),
+            ));
+        }
+
+        state.write.flush()?;
+        Ok(state.write)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread};
+
+    use super::*;
+    use crate::{Chunk, LineChunks};
+
+    #[test]
+    fn test_ordered_sink() {
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        let mut sink = OrderedSink::new(Vec::new());
+        sink.memory_limit(1000);
+
+        // Hand chunks out in order to workers, which finish out of order.
+        let (send, recv) = mpsc::sync_channel::<Chunk>(4);
+        let recv = Mutex::new(recv);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| loop {
+                    let Ok(chunk) = recv.lock().unwrap().recv() else {
+                        break;
+                    };
+                    if chunk.index.is_multiple_of(3) {
+                        thread::yield_now();
+                    }
+                    let upper = chunk.data.to_ascii_uppercase();
+                    sink.write_chunk(chunk.index, upper).unwrap();
+                });
+            }
+            for chunk in LineChunks::new(64, input.as_bytes()).with_metadata() {
+                send.send(chunk.unwrap()).unwrap();
+            }
+            drop(send);
+        });
+
+        assert_eq!(sink.finish().unwrap(), input.as_bytes());
+
+        let sink = OrderedSink::new(Vec::new());
+        sink.write_chunk(1, b"b".to_vec()).unwrap();
+        assert!(sink.write_chunk(1, b"b".to_vec()).is_err());
+        assert!(sink.finish().is_err());
+    }
+}
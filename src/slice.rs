@@ -0,0 +1,83 @@
+/// Split an in-memory buffer into line-aligned chunks.
+///
+/// This is the infallible, zero-copy counterpart of
+/// [`LineChunks`](crate::LineChunks) for data which is already in memory:
+/// chunks are borrowed slices of the input, and there are no IO errors to
+/// handle. Chunks are normally up to `chunksize` bytes, ending at the last
+/// `\n` before that, and at least the minimum chunk size (75% of `chunksize`
+/// by default). A line which doesn't fit is returned whole in a larger chunk.
+pub struct SliceChunks<'a> {
+    data: &'a [u8],
+    chunksize: usize,
+    min_chunk: usize,
+}
+
+impl<'a> SliceChunks<'a> {
+    pub fn new(chunksize: usize, data: &'a [u8]) -> Self {
+        SliceChunks {
+            data,
+            chunksize,
+            min_chunk: chunksize * 3 / 4,
+        }
+    }
+
+    /// Minimum chunk size. The last chunk may be shorter.
+    pub fn min_chunk(&mut self, size: usize) {
+        self.min_chunk = size;
+    }
+}
+
+impl<'a> Iterator for SliceChunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let window = &self.data[..self.chunksize.min(self.data.len())];
+        let len = match memchr::memrchr(b'\n', window) {
+            Some(eol) if eol + 1 >= self.min_chunk => eol + 1,
+            _ => {
+                // Too short, so extend to the first line ending at or after
+                // the minimum size.
+                let from = self.min_chunk.saturating_sub(1).min(self.data.len());
+                memchr::memchr(b'\n', &self.data[from..])
+                    .map_or(self.data.len(), |eol| from + eol + 1)
+            }
+        };
+
+        let (chunk, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slice_chunks() {
+        let input: String = (0..1000)
+            .map(|i| format!("{}\n", "x".repeat(i % 37)))
+            .collect();
+        let chunks: Vec<&[u8]> = SliceChunks::new(100, input.as_bytes()).collect();
+
+        assert_eq!(chunks.concat(), input.as_bytes());
+        for chunk in &chunks {
+            assert!(chunk.ends_with(b"\n"));
+        }
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.len() >= 75, "{}", chunk.len());
+        }
+
+        let long = b"short\na very long line indeed\nend";
+        let chunks: Vec<&[u8]> = SliceChunks::new(8, long).collect();
+        assert_eq!(
+            chunks,
+            [&b"short\n"[..], b"a very long line indeed\n", b"end"]
+        );
+        assert_eq!(SliceChunks::new(8, b"").count(), 0);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{cmp::Ordering, fs::File, io, path::Path};
+
+use rayon::prelude::*;
+
+use crate::{shutdown::Shutdown, splitter, LineChunks};
+
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// The first out-of-order line found by [`verify_sorted`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unsorted {
+    /// Line number, starting at 1.
+    pub line: u64,
+    /// Byte offset of the start of the line.
+    pub offset: u64,
+}
+
+/// What we learn about one chunk.
+struct Part {
+    index: usize,
+    len: usize,
+    lines: u64,
+    first: Vec<u8>,
+    last: Vec<u8>,
+    // Line index and byte offset within the chunk of the first line which is
+    // out of order with its predecessor.
+    unsorted: Option<(u64, usize)>,
+}
+
+impl Part {
+    fn scan<F>(index: usize, chunk: &[u8], cmp: &F) -> Part
+    where
+        F: Fn(&[u8], &[u8]) -> Ordering,
+    {
+        let mut prev: Option<&[u8]> = None;
+        let mut lines = 0;
+        let mut unsorted = None;
+        let mut first = None;
+
+        for span in splitter::spans(chunk) {
+            let line = &chunk[span.start..span.eol];
+
+            if unsorted.is_none() && prev.is_some_and(|prev| cmp(prev, line) == Ordering::Greater) {
+                unsorted = Some((lines, span.start));
+            }
+
+            first.get_or_insert(line);
+            prev = Some(line);
+            lines += 1;
+        }
+
+        Part {
+            index,
+            len: chunk.len(),
+            lines,
+            first: first.unwrap_or_default().to_vec(),
+            last: prev.unwrap_or_default().to_vec(),
+            unsorted,
+        }
+    }
+}
+
+/// Check whether the lines of a file are sorted according to `cmp`.
+///
+/// Each chunk is checked in parallel, then the boundaries between adjacent
+/// chunks are checked. Lines must be non-decreasing: a line comparing equal to
+/// its predecessor is fine. Returns the position of the first line which is
+/// out of order, or `None` if the whole file is sorted.
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// let unsorted = linechunks::verify_sorted("data.txt", |a, b| a.cmp(b))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify_sorted<P, F>(path: P, cmp: F) -> io::Result<Option<Unsorted>>
+where
+    P: AsRef<Path>,
+    F: Fn(&[u8], &[u8]) -> Ordering + Sync,
+{
+    let file = File::open(path)?;
+    let shutdown = Shutdown::default();
+
+    let mut parts = shutdown
+        .source(LineChunks::new(CHUNK_SIZE, file).enumerate())
+        .par_bridge()
+        .map(|(index, chunk)| {
+            let _guard = shutdown.guard();
+            chunk.map(|chunk| Part::scan(index, &chunk, &cmp))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    parts.sort_unstable_by_key(|part| part.index);
+
+    let mut line = 1;
+    let mut offset = 0;
+    let mut prev: Option<&Part> = None;
+
+    for part in &parts {
+        if prev.is_some_and(|prev| cmp(&prev.last, &part.first) == Ordering::Greater) {
+            return Ok(Some(Unsorted { line, offset }));
+        }
+        if let Some((lines, lim)) = part.unsorted {
+            return Ok(Some(Unsorted {
+                line: line + lines,
+                offset: offset + lim as u64,
+            }));
+        }
+
+        line += part.lines;
+        offset += part.len as u64;
+        prev = Some(part);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    fn check(name: &str, data: &[u8]) -> Option<Unsorted> {
+        let path = env::temp_dir().join(format!("linechunks-{}-{name}", process::id()));
+        fs::write(&path, data).unwrap();
+        let res = verify_sorted(&path, |a, b| a.cmp(b)).unwrap();
+        fs::remove_file(&path).unwrap();
+        res
+    }
+
+    #[test]
+    fn test_sorted() {
+        let data: String = (0..500_000).map(|i| format!("{i:08}\n")).collect();
+        assert_eq!(check("sorted", data.as_bytes()), None);
+        assert_eq!(check("empty", b""), None);
+        assert_eq!(check("dup", b"a\na\nb"), None);
+    }
+
+    #[test]
+    fn test_unsorted() {
+        assert_eq!(
+            check("inner", b"a\nc\nb\n"),
+            Some(Unsorted { line: 3, offset: 4 })
+        );
+
+        // Out of order exactly at a chunk boundary
+        let mut data: Vec<u8> = (0..200_000)
+            .flat_map(|i| format!("{i:08}\n").into_bytes())
+            .collect();
+        let boundary = CHUNK_SIZE / 9 * 9;
+        data[boundary..boundary + 9].copy_from_slice(b"00000000\n");
+        let unsorted = check("boundary", &data).unwrap();
+        assert_eq!(unsorted.offset, boundary as u64);
+        assert_eq!(unsorted.line, boundary as u64 / 9 + 1);
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+use crate::hugepage;
+
+type FillBuf<R> = for<'a> fn(&'a mut R) -> io::Result<&'a [u8]>;
+
+/// Where [`LineChunks`](crate::LineChunks) gets its input: either its own
+/// `BufReader`, a buffer of its own allocated with hugepages, or the buffer of
+/// a reader which is already a `BufRead`.
+pub(crate) struct Source<R> {
+    kind: Kind<R>,
+    chunksize: usize,
+}
+
+enum Kind<R> {
+    Buffered(BufReader<R>),
+    // Like `BufReader`, but over a buffer we allocated, so we know where it
+    // is to advise the kernel about it. `buf[pos..filled]` is unconsumed.
+    #[cfg_attr(not(feature = "hugepages"), allow(dead_code))]
+    Owned {
+        read: R,
+        buf: Vec<u8>,
+        pos: usize,
+        filled: usize,
+    },
+    // The `BufRead` methods, captured when the reader is known to have them.
+    Direct {
+        read: R,
+        fill_buf: FillBuf<R>,
+        consume: fn(&mut R, usize),
+    },
+}
+
+impl<R: Read> Source<R> {
+    pub(crate) fn buffered(chunksize: usize, read: R) -> Self {
+        Source {
+            kind: Kind::Buffered(BufReader::with_capacity(chunksize, read)),
+            chunksize,
+        }
+    }
+
+    /// A buffer of our own, backed by hugepages if possible.
+    #[cfg_attr(not(feature = "hugepages"), allow(dead_code))]
+    pub(crate) fn hugepages(chunksize: usize, read: R) -> Self {
+        let mut buf = hugepage::alloc(chunksize, true);
+        buf.resize(chunksize, 0);
+        Source {
+            kind: Kind::Owned {
+                read,
+                buf,
+                pos: 0,
+                filled: 0,
+            },
+            chunksize,
+        }
+    }
+
+    pub(crate) fn direct(chunksize: usize, read: R) -> Self
+    where
+        R: BufRead,
+    {
+        Source {
+            kind: Kind::Direct {
+                read,
+                fill_buf: R::fill_buf,
+                consume: R::consume,
+            },
+            chunksize,
+        }
+    }
+
+    pub(crate) fn chunksize(&self) -> usize {
+        self.chunksize
+    }
+
+    pub(crate) fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match &mut self.kind {
+            Kind::Buffered(buffer) => buffer.fill_buf(),
+            Kind::Owned {
+                read,
+                buf,
+                pos,
+                filled,
+            } => {
+                if *pos == *filled {
+                    *filled = read.read(buf)?;
+                    *pos = 0;
+                }
+                Ok(&buf[*pos..*filled])
+            }
+            Kind::Direct { read, fill_buf, .. } => fill_buf(read),
+        }
+    }
+
+    pub(crate) fn consume(&mut self, amt: usize) {
+        match &mut self.kind {
+            Kind::Buffered(buffer) => buffer.consume(amt),
+            Kind::Owned { pos, filled, .. } => *pos = (*pos + amt).min(*filled),
+            Kind::Direct { read, consume, .. } => consume(read, amt),
+        }
+    }
+
+    /// Data in our own buffer which hasn't been consumed. A `BufRead` reader
+    /// keeps its own.
+    pub(crate) fn buffer(&self) -> &[u8] {
+        match &self.kind {
+            Kind::Buffered(buffer) => buffer.buffer(),
+            Kind::Owned {
+                buf, pos, filled, ..
+            } => &buf[*pos..*filled],
+            Kind::Direct { .. } => &[],
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> R {
+        match self.kind {
+            Kind::Buffered(buffer) => buffer.into_inner(),
+            Kind::Owned { read, .. } | Kind::Direct { read, .. } => read,
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        match &self.kind {
+            Kind::Buffered(buffer) => buffer.get_ref(),
+            Kind::Owned { read, .. } | Kind::Direct { read, .. } => read,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        match &mut self.kind {
+            Kind::Buffered(buffer) => buffer.get_mut(),
+            Kind::Owned { read, .. } | Kind::Direct { read, .. } => read,
+        }
+    }
+
+    pub(crate) fn seek_relative(&mut self, offset: i64) -> io::Result<()>
+    where
+        R: Seek,
+    {
+        match &mut self.kind {
+            Kind::Buffered(buffer) => buffer.seek_relative(offset),
+            Kind::Owned {
+                read, pos, filled, ..
+            } => {
+                // Stay within the buffer if we can, as BufReader does.
+                let target = *pos as i64 + offset;
+                if (0..=*filled as i64).contains(&target) {
+                    *pos = target as usize;
+                } else {
+                    read.seek(SeekFrom::Current(offset - (*filled - *pos) as i64))?;
+                    *pos = 0;
+                    *filled = 0;
+                }
+                Ok(())
+            }
+            Kind::Direct { read, .. } => read.seek(SeekFrom::Current(offset)).map(drop),
+        }
+    }
+}
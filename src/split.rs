@@ -0,0 +1,206 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+};
+
+use crate::LineChunks;
+
+/// Return the offset of the first line starting at or after `pos`.
+pub(crate) fn next_line_start<R: Read + Seek>(reader: &mut R, pos: u64) -> io::Result<u64> {
+    if pos == 0 {
+        return Ok(0);
+    }
+
+    // Start from the byte before so that if `pos` is already at the start of
+    // a line we return it.
+    reader.seek(SeekFrom::Start(pos - 1))?;
+    let mut skipped = Vec::new();
+    let len = BufReader::new(reader).read_until(b'\n', &mut skipped)?;
+
+    Ok(pos - 1 + len as u64)
+}
+
+/// Divide the first `len` bytes of `reader` into line-aligned ranges of
+/// roughly `target` bytes, by probing at each multiple of `target` and
+/// realigning to the next line start. A line longer than `target` will make
+/// its range correspondingly larger.
+pub(crate) fn split_ranges<R: Read + Seek>(
+    reader: &mut R,
+    len: u64,
+    target: u64,
+) -> io::Result<Vec<Range<u64>>> {
+    let target = target.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    while start < len {
+        let end = if len - start > target {
+            next_line_start(reader, start + target)?.min(len)
+        } else {
+            len
+        };
+        ranges.push(start..end);
+        start = end;
+    }
+
+    Ok(ranges)
+}
+
+impl<R: Read + Seek> LineChunks<io::Take<R>> {
+    /// Chunk the lines of `read` which start in the byte range `start..end`.
+    ///
+    /// If `start` is partway through a line, that line is skipped, and the
+    /// line which spans `end` is read to its end. So a set of contiguous
+    /// ranges, as assigned to workers in a distributed job, gives each line
+    /// to exactly one of them, wherever the range boundaries fall. The
+    /// boundaries are found by probing `read` before chunking starts, and
+    /// chunk offsets are positions in the whole of `read`.
+    ///
+    /// ```
+    /// use linechunks::LineChunks;
+    ///
+    /// let input = std::io::Cursor::new(b"one\ntwo\nthree\n");
+    /// let chunks: Vec<_> = LineChunks::range(64, input, 2, 5)?.collect::<Result<_, _>>()?;
+    /// assert_eq!(chunks, vec![b"two\n".to_vec()]);
+    /// # Ok::<_, std::io::Error>(())
+    /// ```
+    pub fn range(chunksize: usize, mut read: R, start: u64, end: u64) -> io::Result<Self> {
+        let start = next_line_start(&mut read, start)?;
+        let end = next_line_start(&mut read, end)?.max(start);
+        read.seek(SeekFrom::Start(start))?;
+
+        let mut chunks = LineChunks::new(chunksize, read.take(end - start));
+        // Offsets count from the start of `read`, so the expected length does
+        // too.
+        chunks.offset = start;
+        chunks.len_hint(end);
+        Ok(chunks)
+    }
+}
+
+/// Find `n + 1` line-aligned split points dividing `file` into `n` ranges of
+/// roughly equal size.
+///
+/// The file is probed at `n` evenly spaced offsets, and each is moved forward
+/// to the start of the next line. The first point is 0 and the last is the
+/// length of the file, so `points.windows(2)` gives the ranges, which can each
+/// be read by an independent reader. A range is empty if a line spans the
+/// whole of it. `n` of 0 is treated as 1. The file's position is left
+/// wherever probing it leaves it.
+///
+/// ```
+/// let mut file = std::io::Cursor::new(b"aaaa\nbb\ncccccccccc\nd\n");
+/// let points = linechunks::split_points(&mut file, 2)?;
+/// assert_eq!(points, vec![0, 19, 21]);
+/// # Ok::<_, std::io::Error>(())
+/// ```
+pub fn split_points<R: Read + Seek>(file: &mut R, n: usize) -> io::Result<Vec<u64>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let n = n.max(1) as u64;
+    let mut points = vec![0];
+
+    for i in 1..n {
+        let probe = (len as u128 * i as u128 / n as u128) as u64;
+        let point = next_line_start(file, probe)?.min(len);
+        // A long line can carry one point past the next probe.
+        points.push(point.max(*points.last().unwrap_or(&0)));
+    }
+    points.push(len);
+
+    Ok(points)
+}
+
+/// Divide a file into line-aligned byte ranges of roughly `target` bytes.
+///
+/// The ranges are contiguous and cover the whole file. Only the bytes around
+/// each split point are read, so this is cheap even for very large files.
+/// The ranges can be handed to other threads, processes or machines to read
+/// with a seek and a bounded read, for example with [`LineChunks`] over
+/// [`Read::take`].
+///
+/// [`LineChunks`]: crate::LineChunks
+pub fn plan_splits<P: AsRef<Path>>(path: P, target: u64) -> io::Result<Vec<Range<u64>>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    split_ranges(&mut file, len, target)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_split_ranges() {
+        let input = b"aaaa\nbb\ncccccccccc\nd\ne\n";
+        let mut reader = Cursor::new(&input[..]);
+
+        let ranges = split_ranges(&mut reader, input.len() as u64, 6).unwrap();
+        assert_eq!(ranges, vec![0..8, 8..19, 19..23]);
+        for range in &ranges[1..] {
+            assert_eq!(input[range.start as usize - 1], b'\n');
+        }
+
+        assert_eq!(split_ranges(&mut reader, 0, 6).unwrap(), vec![]);
+        assert_eq!(split_ranges(&mut reader, 23, 100).unwrap(), vec![0..23]);
+    }
+
+    #[test]
+    fn test_split_points() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let mut reader = Cursor::new(input.as_bytes());
+
+        let points = split_points(&mut reader, 7).unwrap();
+        assert_eq!(points.len(), 8);
+        assert_eq!(points[7], input.len() as u64);
+        for pair in points.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert_eq!(input.as_bytes()[pair[1] as usize - 1], b'\n');
+        }
+
+        let mut reader = Cursor::new(&b"aaaaaaaaaaaaaaaaaaaa\nb\n"[..]);
+        assert_eq!(
+            split_points(&mut reader, 4).unwrap(),
+            vec![0, 21, 21, 21, 23]
+        );
+        assert_eq!(split_points(&mut reader, 0).unwrap(), vec![0, 23]);
+    }
+
+    #[test]
+    fn test_range() {
+        let input = b"aaaa\nbb\ncccccccccc\nd\ne";
+
+        // Each line is in exactly one of a set of contiguous ranges.
+        let mut lines = Vec::new();
+        for range in [0..3, 3..5, 5..6, 6..20, 20..100] {
+            let mut chunks =
+                LineChunks::range(4, Cursor::new(&input[..]), range.start, range.end).unwrap();
+            chunks.min_chunk(1);
+            let mut offset = None;
+            for chunk in chunks.with_metadata() {
+                let chunk = chunk.unwrap();
+                offset.get_or_insert(chunk.offset);
+                lines.extend(chunk.lines().map(<[u8]>::to_vec));
+            }
+            if let Some(offset) = offset {
+                assert!(offset == 0 || input[offset as usize - 1] == b'\n');
+            }
+        }
+
+        let expected: Vec<_> = input.split(|&c| c == b'\n').map(<[u8]>::to_vec).collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_plan_splits() {
+        let path = std::env::temp_dir().join(format!("linechunks-splits-{}", std::process::id()));
+        std::fs::write(&path, b"aaaa\nbb\ncccccccccc\nd\ne\n").unwrap();
+        let ranges = plan_splits(&path, 6).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(ranges, vec![0..8, 8..19, 19..23]);
+    }
+}
@@ -0,0 +1,181 @@
+use std::ops::Range;
+
+/// How lines are trimmed before they're passed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Trim {
+    None,
+    Crlf,
+    Whitespace,
+}
+
+/// Where one line is in a chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Span {
+    /// Start of the line.
+    pub(crate) start: usize,
+    /// End of the line, before its terminator.
+    pub(crate) eol: usize,
+    /// Past the line's terminator, if it has one.
+    pub(crate) next: usize,
+}
+
+/// Splits a chunk into lines, from either end, with the options shared by
+/// [`LineSplitParse`](crate::LineSplitParse) and the other line iterators.
+///
+/// The splitter only holds the position, so the chunk is passed to each
+/// call. [`next_span`](Self::next_span) yields every line, whatever the
+/// options; [`next_line`](Self::next_line) applies them, returning the part
+/// of each line to pass on and skipping lines which should be.
+#[derive(Clone, Debug)]
+pub(crate) struct Splitter {
+    pub(crate) delim: u8,
+    pub(crate) keep_terminator: bool,
+    pub(crate) keep_empty: bool,
+    pub(crate) trim: Trim,
+    lim: usize,
+    end: usize,
+}
+
+impl Splitter {
+    /// Split a chunk of `len` bytes on `\n`, skipping empty lines and
+    /// passing lines without their terminator.
+    pub(crate) fn new(len: usize) -> Self {
+        Splitter {
+            delim: b'\n',
+            keep_terminator: false,
+            keep_empty: false,
+            trim: Trim::None,
+            lim: 0,
+            end: len,
+        }
+    }
+
+    /// The next line from the front.
+    pub(crate) fn next_span(&mut self, buf: &[u8]) -> Option<Span> {
+        if self.lim == self.end {
+            return None;
+        }
+
+        let start = self.lim;
+        let (eol, next) = memchr::memchr(self.delim, &buf[start..self.end])
+            .map(|eol| (start + eol, start + eol + 1))
+            .unwrap_or((self.end, self.end));
+        self.lim = next;
+        Some(Span { start, eol, next })
+    }
+
+    /// The next line from the back.
+    pub(crate) fn next_back_span(&mut self, buf: &[u8]) -> Option<Span> {
+        if self.lim == self.end {
+            return None;
+        }
+
+        let next = self.end;
+        let eol = if buf[next - 1] == self.delim {
+            next - 1
+        } else {
+            next
+        };
+        let start = memchr::memrchr(self.delim, &buf[self.lim..eol])
+            .map(|pos| self.lim + pos + 1)
+            .unwrap_or(self.lim);
+        self.end = start;
+        Some(Span { start, eol, next })
+    }
+
+    /// The part of the line at `span` to pass on, or `None` if it should be
+    /// skipped.
+    pub(crate) fn line(&self, buf: &[u8], span: Span) -> Option<Range<usize>> {
+        let range = if self.keep_terminator {
+            span.start..span.next
+        } else {
+            let line = &buf[span.start..span.eol];
+            let trimmed = match self.trim {
+                Trim::None => line,
+                Trim::Crlf => line.strip_suffix(b"\r").unwrap_or(line),
+                Trim::Whitespace => line.trim_ascii(),
+            };
+            let offset = trimmed.as_ptr() as usize - line.as_ptr() as usize;
+            span.start + offset..span.start + offset + trimmed.len()
+        };
+
+        let line = &buf[range.clone()];
+        let empty = line.is_empty() || line == [self.delim];
+        (!empty || self.keep_empty).then_some(range)
+    }
+
+    /// The next line to pass on from the front, with its span.
+    pub(crate) fn next_line(&mut self, buf: &[u8]) -> Option<(Span, Range<usize>)> {
+        loop {
+            let span = self.next_span(buf)?;
+            if let Some(range) = self.line(buf, span) {
+                break Some((span, range));
+            }
+        }
+    }
+
+    /// The next line to pass on from the back, with its span.
+    pub(crate) fn next_back_line(&mut self, buf: &[u8]) -> Option<(Span, Range<usize>)> {
+        loop {
+            let span = self.next_back_span(buf)?;
+            if let Some(range) = self.line(buf, span) {
+                break Some((span, range));
+            }
+        }
+    }
+}
+
+/// Every line of a chunk, as [`Span`]s.
+pub(crate) fn spans(buf: &[u8]) -> impl Iterator<Item = Span> + '_ {
+    let mut split = Splitter::new(buf.len());
+    std::iter::from_fn(move || split.next_span(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(split: &mut Splitter, buf: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        while let Some((_, range)) = split.next_line(buf) {
+            out.push(String::from_utf8_lossy(&buf[range]).into_owned());
+        }
+        out
+    }
+
+    #[test]
+    fn test_splitter() {
+        let buf = b" a \r\n\nb\r\nc";
+
+        assert_eq!(
+            lines(&mut Splitter::new(buf.len()), buf),
+            [" a \r", "b\r", "c"]
+        );
+
+        let mut split = Splitter::new(buf.len());
+        split.trim = Trim::Crlf;
+        split.keep_empty = true;
+        assert_eq!(lines(&mut split, buf), [" a ", "", "b", "c"]);
+
+        let mut split = Splitter::new(buf.len());
+        split.trim = Trim::Whitespace;
+        assert_eq!(lines(&mut split, buf), ["a", "b", "c"]);
+
+        let mut split = Splitter::new(buf.len());
+        split.keep_terminator = true;
+        split.keep_empty = true;
+        assert_eq!(lines(&mut split, buf).concat().as_bytes(), buf);
+
+        let mut split = Splitter::new(buf.len());
+        let mut back = Vec::new();
+        while let Some((_, range)) = split.next_back_line(buf) {
+            back.push(&buf[range]);
+        }
+        assert_eq!(back, [&b"c"[..], b"b\r", b" a \r"]);
+
+        let spans: Vec<_> = spans(b"x\n\ny")
+            .map(|span| (span.start, span.eol, span.next))
+            .collect();
+        assert_eq!(spans, [(0, 1, 2), (2, 2, 3), (3, 4, 4)]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::io;
+
+use rusqlite::{Connection, Params};
+
+use crate::LineSplitParse;
+
+/// Whether [`SqliteSink`] inserts rows in input order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadOrder {
+    /// Parse and insert one chunk at a time, in input order.
+    #[default]
+    Ordered,
+    /// Parse chunks in parallel and insert them as they're ready. Within a
+    /// chunk rows are still in order. Without the `rayon` feature, this loads
+    /// in order.
+    Unordered,
+}
+
+/// Bulk-load parsed lines into SQLite.
+///
+/// Each line is parsed into the parameters for an `INSERT` statement, and the
+/// rows for each chunk are inserted within one transaction. So a failure
+/// leaves whole chunks loaded or not, never part of one.
+pub struct SqliteSink<'a> {
+    conn: &'a mut Connection,
+    sql: String,
+    order: LoadOrder,
+}
+
+fn sql_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+impl<'a> SqliteSink<'a> {
+    /// Insert rows with `sql`, such as `INSERT INTO logs VALUES (?1, ?2)`.
+    pub fn new(conn: &'a mut Connection, sql: &str) -> Self {
+        SqliteSink {
+            conn,
+            sql: sql.to_string(),
+            order: LoadOrder::default(),
+        }
+    }
+
+    /// Set the [`LoadOrder`].
+    pub fn order(&mut self, order: LoadOrder) {
+        self.order = order;
+    }
+
+    /// Insert a chunk's rows in a transaction, returning how many there were.
+    fn insert<T: Params>(&mut self, rows: Vec<T>) -> io::Result<u64> {
+        let len = rows.len() as u64;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        {
+            let mut stmt = tx.prepare_cached(&self.sql).map_err(sql_error)?;
+            for row in rows {
+                stmt.execute(row).map_err(sql_error)?;
+            }
+        }
+        tx.commit().map_err(sql_error)?;
+
+        Ok(len)
+    }
+
+    /// Load each non-empty line of `chunks` for which `parse` returns a row.
+    /// Returns the number of rows inserted, or the first error from the input
+    /// or from SQLite.
+    pub fn load<I, P, T>(&mut self, chunks: I, parse: P) -> io::Result<u64>
+    where
+        I: Iterator<Item = io::Result<Vec<u8>>> + Send,
+        P: Fn(&[u8]) -> Option<T> + Sync,
+        T: Params + Send,
+    {
+        match self.order {
+            LoadOrder::Ordered => self.load_ordered(chunks, parse),
+            #[cfg(feature = "rayon")]
+            LoadOrder::Unordered => self.load_unordered(chunks, parse),
+            #[cfg(not(feature = "rayon"))]
+            LoadOrder::Unordered => self.load_ordered(chunks, parse),
+        }
+    }
+
+    fn load_ordered<I, P, T>(&mut self, chunks: I, parse: P) -> io::Result<u64>
+    where
+        I: Iterator<Item = io::Result<Vec<u8>>>,
+        P: Fn(&[u8]) -> Option<T>,
+        T: Params,
+    {
+        let mut rows = 0;
+        for chunk in chunks {
+            rows += self.insert(LineSplitParse::new(chunk?, &parse).flatten().collect())?;
+        }
+        Ok(rows)
+    }
+
+    /// Parse on the rayon pool, and insert on this thread since the
+    /// connection can't be shared.
+    #[cfg(feature = "rayon")]
+    fn load_unordered<I, P, T>(&mut self, chunks: I, parse: P) -> io::Result<u64>
+    where
+        I: Iterator<Item = io::Result<Vec<u8>>> + Send,
+        P: Fn(&[u8]) -> Option<T> + Sync,
+        T: Params + Send,
+    {
+        use rayon::prelude::*;
+        use std::{sync::mpsc, thread};
+
+        use crate::shutdown::Shutdown;
+
+        let (tx, rx) = mpsc::sync_channel(rayon::current_num_threads());
+        let shutdown = Shutdown::default();
+
+        thread::scope(|scope| {
+            let parser = scope.spawn(|| {
+                shutdown
+                    .source(chunks)
+                    .par_bridge()
+                    .try_for_each_with(tx, |tx, chunk| {
+                        let _guard = shutdown.guard();
+                        let rows: Vec<T> = LineSplitParse::new(chunk?, &parse).flatten().collect();
+                        // The receiver only goes away after an insert error,
+                        // which is what's returned.
+                        tx.send(rows)
+                            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+                    })
+            });
+
+            // The receiver is dropped when this loop ends, so after an error
+            // the parser stops once it next tries to send.
+            let mut total = 0;
+            let mut res = Ok(());
+            for rows in rx {
+                match self.insert(rows) {
+                    Ok(n) => total += n,
+                    Err(err) => {
+                        res = Err(err);
+                        break;
+                    }
+                }
+            }
+
+            let parsed = match parser.join() {
+                Ok(parsed) => parsed,
+                Err(panic) => std::panic::resume_unwind(panic),
+            };
+            res.and(parsed).map(|()| total)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    fn parse(line: &[u8]) -> Option<(String, i64)> {
+        let line = std::str::from_utf8(line).ok()?;
+        let (name, n) = line.split_once(' ')?;
+        Some((name.to_string(), n.parse().ok()?))
+    }
+
+    fn load(order: LoadOrder) -> Connection {
+        let input: String = (0..1000).map(|i| format!("key{} {i}\n", i % 7)).collect();
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE t (name TEXT, n INTEGER)", ())
+            .unwrap();
+
+        let mut sink = SqliteSink::new(&mut conn, "INSERT INTO t VALUES (?1, ?2)");
+        sink.order(order);
+        let chunks = LineChunks::new(256, input.as_bytes());
+        assert_eq!(sink.load(chunks, parse).unwrap(), 1000);
+        conn
+    }
+
+    fn sum(conn: &Connection) -> i64 {
+        conn.query_row("SELECT SUM(n) FROM t", (), |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ordered() {
+        let conn = load(LoadOrder::Ordered);
+        assert_eq!(sum(&conn), 499500);
+
+        let first: Vec<i64> = conn
+            .prepare("SELECT n FROM t ORDER BY rowid LIMIT 3")
+            .unwrap()
+            .query_map((), |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(first, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_unordered() {
+        let conn = load(LoadOrder::Unordered);
+        assert_eq!(sum(&conn), 499500);
+    }
+
+    #[test]
+    fn test_error() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut sink = SqliteSink::new(&mut conn, "INSERT INTO missing VALUES (?1, ?2)");
+        assert!(sink
+            .load(LineChunks::new(16, &b"a 1\n"[..]), parse)
+            .is_err());
+    }
+}
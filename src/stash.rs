@@ -0,0 +1,67 @@
+/// Yield plain values from an iterator of `Result`s, stashing the error.
+///
+/// This is for adapters and combinators which want an `Iterator<Item = T>`
+/// rather than `Iterator<Item = Result<T, E>>`. Iteration stops at the first
+/// error, which can then be retrieved with [`take_error`](Self::take_error).
+/// Iterate with `by_ref()` to keep access to it, and check it once iteration
+/// is done: otherwise a failed read looks the same as the end of the input.
+pub struct StashErrors<I, E> {
+    iter: I,
+    error: Option<E>,
+}
+
+impl<I, T, E> StashErrors<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    pub fn new(iter: I) -> Self {
+        StashErrors { iter, error: None }
+    }
+
+    /// Take the error which ended iteration, if any.
+    pub fn take_error(&mut self) -> Option<E> {
+        self.error.take()
+    }
+}
+
+impl<I, T, E> Iterator for StashErrors<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.iter.next()? {
+            Ok(v) => Some(v),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, io};
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[test]
+    fn test_stash_errors() {
+        let mut chunks = StashErrors::new(LineChunks::new(4, &b"a\nb\nc\n"[..]));
+        assert_eq!(chunks.by_ref().flatten().count(), 6);
+        assert!(chunks.take_error().is_none());
+
+        let file = File::open("/dev/zero").expect("/dev/zero open failed");
+        let mut chunks = StashErrors::new(LineChunks::new(8192, file));
+        assert_eq!(chunks.by_ref().count(), 0);
+        let err: io::Error = chunks.take_error().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{self, Read, Write};
+
+use crate::{Delimiter, LineChunks};
+
+/// Copy every chunk to a writer as it's yielded, from [`LineChunks::tee`].
+///
+/// Chunks are written in input order before they're yielded, so the writer
+/// sees exactly the chunked input even if the chunks are then processed out
+/// of order. The writer is flushed at the end of the input. An error writing
+/// a chunk is yielded in its place and ends iteration, as does a read error
+/// after it's been yielded.
+pub struct Tee<R, D, W> {
+    chunks: LineChunks<R, D>,
+    write: W,
+    finished: bool,
+}
+
+impl<R, D, W> Tee<R, D, W> {
+    /// The underlying `LineChunks`, for its error report and other state.
+    pub fn get_ref(&self) -> &LineChunks<R, D> {
+        &self.chunks
+    }
+
+    /// The writer.
+    pub fn writer(&mut self) -> &mut W {
+        &mut self.write
+    }
+
+    pub fn into_parts(self) -> (LineChunks<R, D>, W) {
+        (self.chunks, self.write)
+    }
+}
+
+impl<R: Read, D: Delimiter, W: Write> Iterator for Tee<R, D, W> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let res = match self.chunks.next() {
+            Some(Ok(chunk)) => self.write.write_all(&chunk).map(|()| chunk),
+            Some(Err(err)) => Err(err),
+            None => {
+                self.finished = true;
+                return self.write.flush().err().map(Err);
+            }
+        };
+        self.finished = res.is_err();
+        Some(res)
+    }
+}
+
+impl<R: Read, D: Delimiter> LineChunks<R, D> {
+    /// Write each chunk to `write` before yielding it, for example to keep a
+    /// copy of the input or to hash it.
+    ///
+    /// ```
+    /// let mut copy = Vec::new();
+    /// let input = &b"a\nb\n"[..];
+    /// let count = linechunks::LineChunks::new(2, input).tee(&mut copy).count();
+    /// assert_eq!((count, &copy[..]), (2, input));
+    /// ```
+    pub fn tee<W: Write>(self, write: W) -> Tee<R, D, W> {
+        Tee {
+            chunks: self,
+            write,
+            finished: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tee() {
+        let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+        let mut tee = LineChunks::new(64, input.as_bytes()).tee(Vec::new());
+
+        let chunks: Vec<_> = tee.by_ref().map(Result::unwrap).collect();
+        assert_eq!(chunks.concat(), input.as_bytes());
+        assert_eq!(tee.into_parts().1, input.as_bytes());
+
+        // Nowhere to write the second chunk.
+        let mut buf = [0; 3];
+        let results: Vec<_> = LineChunks::new(2, &b"a\nb\nc\n"[..])
+            .tee(&mut buf[..])
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    splitter::{Splitter, Trim},
+    Chunk, LinePos,
+};
+
+/// A parse error from [`TryLineSplitParse`], with where it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError<E> {
+    /// Where the line is.
+    pub pos: LinePos,
+    /// The line which failed to parse.
+    pub line: Vec<u8>,
+    /// The error from the parser.
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for ParseError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pos.absolute {
+            Some(offset) => write!(f, "parse error at offset {offset}: {}", self.error),
+            None => write!(f, "parse error on line {}: {}", self.pos.line, self.error),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ParseError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Like [`LineSplitParse`](crate::LineSplitParse) with a fallible parser, but
+/// errors carry the position and text of the line which failed.
+///
+/// By default iteration stops after the first error. With
+/// [`stop_on_error(false)`](Self::stop_on_error) it carries on, so all the
+/// errors in a chunk can be collected.
+pub struct TryLineSplitParse<F> {
+    buf: Vec<u8>,
+    split: Splitter,
+    line: u64,
+    base: Option<u64>,
+    stop_on_error: bool,
+    finished: bool,
+    parser: F,
+}
+
+impl<F> TryLineSplitParse<F> {
+    pub fn new(buf: Vec<u8>, parser: F) -> Self {
+        TryLineSplitParse {
+            split: Splitter::new(buf.len()),
+            buf,
+            line: 0,
+            base: None,
+            stop_on_error: true,
+            finished: false,
+            parser,
+        }
+    }
+
+    /// Split a [`Chunk`] from [`LineChunks::with_metadata`](crate::LineChunks::with_metadata),
+    /// so errors have absolute offsets.
+    pub fn from_chunk(chunk: Chunk, parser: F) -> Self {
+        let mut split = TryLineSplitParse::new(chunk.data, parser);
+        split.base = Some(chunk.offset);
+        split
+    }
+
+    /// Strip the `\r` from lines ending in `\r\n`, as for
+    /// [`LineSplitParse::trim_crlf`](crate::LineSplitParse::trim_crlf).
+    pub fn trim_crlf(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Crlf } else { Trim::None };
+    }
+
+    /// Strip ASCII whitespace from both ends of each line, as for
+    /// [`LineSplitParse::trim_whitespace`](crate::LineSplitParse::trim_whitespace).
+    pub fn trim_whitespace(&mut self, trim: bool) {
+        self.split.trim = if trim { Trim::Whitespace } else { Trim::None };
+    }
+
+    /// Pass lines to the parser with their terminator, if they have one.
+    pub fn keep_terminator(&mut self, keep: bool) {
+        self.split.keep_terminator = keep;
+    }
+
+    /// Pass empty lines to the parser, rather than skipping them.
+    pub fn keep_empty(&mut self, keep: bool) {
+        self.split.keep_empty = keep;
+    }
+
+    /// Split lines on `delim` rather than `\n`.
+    pub fn delimiter(&mut self, delim: u8) {
+        self.split.delim = delim;
+    }
+
+    /// Whether to stop after the first error.
+    pub fn stop_on_error(&mut self, stop: bool) {
+        self.stop_on_error = stop;
+    }
+}
+
+impl<F, T, E> Iterator for TryLineSplitParse<F>
+where
+    F: FnMut(&[u8]) -> Result<T, E>,
+{
+    type Item = Result<T, ParseError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            let span = self.split.next_span(&self.buf)?;
+            let line = self.line;
+            self.line += 1;
+
+            let Some(range) = self.split.line(&self.buf, span) else {
+                continue;
+            };
+            let slice = &self.buf[range];
+
+            break Some((self.parser)(slice).map_err(|error| {
+                self.finished = self.stop_on_error;
+                ParseError {
+                    pos: LinePos {
+                        line,
+                        offset: span.start,
+                        absolute: self.base.map(|base| base + span.start as u64),
+                    },
+                    line: slice.to_vec(),
+                    error,
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::ParseIntError;
+
+    use super::*;
+
+    fn parse(line: &[u8]) -> Result<u32, ParseIntError> {
+        String::from_utf8_lossy(line).parse()
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let chunk = b"1\n2\nx\n\n4\ny\n".to_vec();
+
+        let results: Vec<_> = TryLineSplitParse::new(chunk.clone(), parse).collect();
+        assert_eq!(results.len(), 3);
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.pos.line, 2);
+        assert_eq!(err.pos.offset, 4);
+        assert_eq!(err.line, b"x");
+
+        let chunk = Chunk {
+            index: 0,
+            offset: 1000,
+            lines: 6,
+            data: chunk,
+        };
+        let mut split = TryLineSplitParse::from_chunk(chunk, parse);
+        split.stop_on_error(false);
+        let errors: Vec<_> = split.filter_map(Result::err).collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].pos.absolute, Some(1009));
+        assert_eq!(errors[1].pos.line, 5);
+        assert!(errors[1]
+            .to_string()
+            .starts_with("parse error at offset 1009"));
+
+        // The splitting options behave as for `LineSplitParse`.
+        let chunk = b" 1 \r\n\n2\r\n".to_vec();
+        let mut split = TryLineSplitParse::new(chunk.clone(), parse);
+        split.trim_whitespace(true);
+        assert_eq!(split.map(Result::unwrap).collect::<Vec<_>>(), [1, 2]);
+
+        let mut split = TryLineSplitParse::new(chunk.clone(), parse);
+        split.trim_crlf(true);
+        split.keep_empty(true);
+        split.stop_on_error(false);
+        let errors: Vec<_> = split.filter_map(Result::err).collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[1].pos.line, &errors[1].line[..]), (1, &b""[..]));
+
+        let mut split = TryLineSplitParse::new(chunk, |line: &[u8]| Ok::<_, ()>(line.len()));
+        split.keep_terminator(true);
+        assert_eq!(split.map(Result::unwrap).sum::<usize>(), 8);
+    }
+}
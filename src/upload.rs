@@ -0,0 +1,219 @@
+use std::{io, mem};
+
+/// A remote object store with multipart uploads, for [`ShardedUpload`].
+pub trait ObjectStore {
+    /// An upload in progress.
+    type Upload;
+
+    /// Start a multipart upload to the object `key`.
+    fn start(&self, key: &str) -> io::Result<Self::Upload>;
+
+    /// Upload part number `part`, counting from 1. Every part except the last
+    /// is at least the configured part size.
+    fn upload_part(&self, upload: &mut Self::Upload, part: u32, data: Vec<u8>) -> io::Result<()>;
+
+    /// Complete the upload, making the object visible.
+    fn complete(&self, upload: Self::Upload) -> io::Result<()>;
+
+    /// Abandon the upload after an error. Errors from this are ignored.
+    fn abort(&self, upload: Self::Upload) -> io::Result<()>;
+}
+
+struct Shard<U> {
+    upload: U,
+    part: u32,
+    len: u64,
+}
+
+/// Upload a stream of chunks as a series of sharded objects.
+///
+/// Chunks are packed whole into parts of at least `part_size` bytes, and a
+/// new object is started once the current one reaches `shard_size`, so
+/// parts and objects both end at chunk (and so line) boundaries. Objects are
+/// named `{prefix}-00000`, `{prefix}-00001` and so on.
+///
+/// If an upload fails it's aborted, and the error is returned. Objects which
+/// were already completed are left in place.
+pub struct ShardedUpload<S: ObjectStore> {
+    store: S,
+    prefix: String,
+    part_size: usize,
+    shard_size: u64,
+    keys: Vec<String>,
+    shard: Option<Shard<S::Upload>>,
+    buf: Vec<u8>,
+}
+
+impl<S: ObjectStore> ShardedUpload<S> {
+    /// Upload to objects named with `prefix`. Parts default to 8MB, and shards
+    /// to 1GB.
+    pub fn new(store: S, prefix: &str) -> Self {
+        ShardedUpload {
+            store,
+            prefix: prefix.to_string(),
+            part_size: 8 << 20,
+            shard_size: 1 << 30,
+            keys: Vec::new(),
+            shard: None,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Minimum size of each part but the last in an object.
+    pub fn part_size(&mut self, size: usize) {
+        self.part_size = size;
+    }
+
+    /// Size at which an object is completed and the next one started.
+    pub fn shard_size(&mut self, size: u64) {
+        self.shard_size = size;
+    }
+
+    /// Abort the current upload, if any, and return `err`.
+    fn fail(&mut self, err: io::Error) -> io::Error {
+        if let Some(shard) = self.shard.take() {
+            let _ = self.store.abort(shard.upload);
+        }
+        err
+    }
+
+    /// Upload the buffer as the next part, starting an object if needed.
+    fn flush_part(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        if self.shard.is_none() {
+            let key = format!("{}-{:05}", self.prefix, self.keys.len());
+            let upload = self.store.start(&key)?;
+            self.keys.push(key);
+            self.shard = Some(Shard {
+                upload,
+                part: 0,
+                len: 0,
+            });
+        }
+
+        let shard = self.shard.as_mut().expect("no shard");
+        let data = mem::take(&mut self.buf);
+        shard.part += 1;
+        shard.len += data.len() as u64;
+        self.store.upload_part(&mut shard.upload, shard.part, data)
+    }
+
+    /// Complete the current object, if any.
+    fn complete(&mut self) -> io::Result<()> {
+        if let Some(shard) = self.shard.take() {
+            self.store.complete(shard.upload)?;
+        }
+        Ok(())
+    }
+
+    /// Add a chunk to the upload.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(chunk);
+        if self.buf.len() < self.part_size {
+            return Ok(());
+        }
+
+        let res = self.flush_part().and_then(|()| match &self.shard {
+            Some(shard) if shard.len >= self.shard_size => self.complete(),
+            _ => Ok(()),
+        });
+        res.map_err(|err| self.fail(err))
+    }
+
+    /// Upload anything remaining and complete the last object. Returns the
+    /// keys of all the objects written.
+    pub fn finish(mut self) -> io::Result<Vec<String>> {
+        match self.flush_part().and_then(|()| self.complete()) {
+            Ok(()) => Ok(self.keys),
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, sync::Mutex};
+
+    use super::*;
+    use crate::LineChunks;
+
+    #[derive(Default)]
+    struct MemStore {
+        objects: Mutex<BTreeMap<String, Vec<u8>>>,
+        aborted: Mutex<Vec<String>>,
+    }
+
+    impl ObjectStore for &MemStore {
+        type Upload = (String, Vec<Vec<u8>>);
+
+        fn start(&self, key: &str) -> io::Result<Self::Upload> {
+            Ok((key.to_string(), Vec::new()))
+        }
+
+        fn upload_part(
+            &self,
+            upload: &mut Self::Upload,
+            part: u32,
+            data: Vec<u8>,
+        ) -> io::Result<()> {
+            if data.starts_with(b"fail") {
+                return Err(io::Error::other("upload failed"));
+            }
+            assert_eq!(part as usize, upload.1.len() + 1);
+            upload.1.push(data);
+            Ok(())
+        }
+
+        fn complete(&self, (key, parts): Self::Upload) -> io::Result<()> {
+            self.objects.lock().unwrap().insert(key, parts.concat());
+            Ok(())
+        }
+
+        fn abort(&self, (key, _): Self::Upload) -> io::Result<()> {
+            self.aborted.lock().unwrap().push(key);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_upload() {
+        let input: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+        let store = MemStore::default();
+        let mut upload = ShardedUpload::new(&store, "logs");
+        upload.part_size(1000);
+        upload.shard_size(20_000);
+
+        for chunk in LineChunks::new(256, input.as_bytes()) {
+            upload.write_chunk(&chunk.unwrap()).unwrap();
+        }
+        let keys = upload.finish().unwrap();
+
+        let objects = store.objects.into_inner().unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys, objects.keys().cloned().collect::<Vec<_>>());
+        assert_eq!(keys[0], "logs-00000");
+        assert!(objects.values().all(|object| object.ends_with(b"\n")));
+        assert_eq!(
+            objects.into_values().collect::<Vec<_>>().concat(),
+            input.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_upload_abort() {
+        let store = MemStore::default();
+        let mut upload = ShardedUpload::new(&store, "logs");
+        upload.part_size(4);
+
+        upload.write_chunk(b"ok\nok\n").unwrap();
+        assert!(upload.write_chunk(b"fail\n").is_err());
+        assert!(store.objects.lock().unwrap().is_empty());
+        assert_eq!(
+            *store.aborted.lock().unwrap(),
+            vec!["logs-00000".to_string()]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{str::Utf8Error, string::FromUtf8Error};
+
+/// Check whether `bytes` is valid UTF-8. With the `simdutf8` feature this is
+/// SIMD accelerated.
+pub(crate) fn is_valid(bytes: &[u8]) -> bool {
+    #[cfg(feature = "simdutf8")]
+    return simdutf8::basic::from_utf8(bytes).is_ok();
+
+    #[cfg(not(feature = "simdutf8"))]
+    return std::str::from_utf8(bytes).is_ok();
+}
+
+/// Convert `bytes` to a `String`, validating with [`is_valid`]. Invalid input
+/// is rare, so the detailed error is left to std.
+pub(crate) fn into_string(bytes: Vec<u8>) -> Result<String, FromUtf8Error> {
+    if is_valid(&bytes) {
+        // SAFETY: just checked.
+        Ok(unsafe { String::from_utf8_unchecked(bytes) })
+    } else {
+        String::from_utf8(bytes)
+    }
+}
+
+/// Borrow `bytes` as a `&str`, validating with [`is_valid`].
+pub(crate) fn as_str(bytes: &[u8]) -> Result<&str, Utf8Error> {
+    if is_valid(bytes) {
+        // SAFETY: just checked.
+        Ok(unsafe { std::str::from_utf8_unchecked(bytes) })
+    } else {
+        std::str::from_utf8(bytes)
+    }
+}
+
+/// Find where to split `bytes` so as not to cut a UTF-8 character in two:
+/// before an incomplete character at the end, or at the end otherwise.
+/// Invalid input, or a single character filling all of `bytes`, is split at
+/// the end.
+pub(crate) fn char_boundary(bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    // A character is at most 4 bytes, so its first byte is in the last 4.
+    let Some(start) = (len.saturating_sub(4)..len)
+        .rev()
+        .find(|&idx| bytes[idx] & 0xc0 != 0x80)
+    else {
+        return len;
+    };
+
+    let width = match bytes[start] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    };
+    if start > 0 && start + width > len {
+        start
+    } else {
+        len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_utf8() {
+        assert!(is_valid("plain ascii, and ünïcödé ✓".as_bytes()));
+        assert!(!is_valid(b"bad \xff byte"));
+        assert!(!is_valid(b"truncated \xe2\x9c"));
+
+        assert_eq!(into_string(b"ok".to_vec()).unwrap(), "ok");
+        let err = into_string(b"ab\xffcd".to_vec()).unwrap_err();
+        assert_eq!(err.utf8_error().valid_up_to(), 2);
+    }
+
+    #[test]
+    fn test_char_boundary() {
+        let check = "ab✓".as_bytes();
+        assert_eq!(char_boundary(check), 5);
+        assert_eq!(char_boundary(&check[..4]), 2);
+        assert_eq!(char_boundary(&check[..3]), 2);
+        assert_eq!(char_boundary(b"abc"), 3);
+        assert_eq!(char_boundary(&check[2..4]), 2);
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::{self, ErrorKind},
+    str,
+};
+
+use crate::{
+    policy::{ErrorPolicy, ErrorReport, ErrorTracker},
+    splitter, utf8,
+};
+
+/// An invariant violation found by [`Validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// Line number the violation is on, starting at 1.
+    pub line: u64,
+    /// Byte offset of the violation from the start of the input.
+    pub offset: u64,
+    pub kind: ViolationKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The input doesn't end with `\n`.
+    MissingFinalNewline,
+    /// The line is longer than the limit. Contains the line's length, not
+    /// including the `\n`.
+    LineTooLong(usize),
+    /// The line contains a NUL byte.
+    Nul,
+    /// The line is not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} (offset {}): ", self.line, self.offset)?;
+        match self.kind {
+            ViolationKind::MissingFinalNewline => write!(f, "missing final newline"),
+            ViolationKind::LineTooLong(len) => write!(f, "line too long ({len} bytes)"),
+            ViolationKind::Nul => write!(f, "embedded NUL"),
+            ViolationKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+        }
+    }
+}
+
+impl Error for Violation {}
+
+/// Check invariants of a chunk stream as it passes through.
+///
+/// Wraps an iterator of line-aligned chunks (such as [`LineChunks`]) and checks
+/// each line as it goes past. Violations are reported with their line number
+/// and byte offset.
+///
+/// Violations are handled according to an [`ErrorPolicy`]. By default it
+/// fails fast: the first violation is returned as an
+/// [`ErrorKind::InvalidData`] error wrapping the [`Violation`], and iteration
+/// ends. With a tolerant policy chunks are passed through unchanged and the
+/// violations are collected for [`Validate::report`]. Use
+/// `ErrorPolicy::CollectUpTo(usize::MAX)` to lint a whole input.
+///
+/// All checks except the line length limit are enabled by default. A line
+/// may be split across chunks, as with
+/// [`OversizePolicy::SplitMidLine`](crate::OversizePolicy::SplitMidLine),
+/// and is still checked as one line.
+///
+/// [`LineChunks`]: crate::LineChunks
+pub struct Validate<I> {
+    chunks: I,
+    finished: bool,
+    line: u64,
+    offset: u64,
+    // The current line continues into the next chunk, where it started, and
+    // its length so far.
+    partial: bool,
+    line_start: u64,
+    line_len: usize,
+    // Whether the last byte seen was a `\n`.
+    last_newline: bool,
+    violations: ErrorTracker<Violation>,
+    final_newline: bool,
+    max_line: Option<usize>,
+    nul: bool,
+    utf8: bool,
+}
+
+impl<I> Validate<I>
+where
+    I: Iterator<Item = io::Result<Vec<u8>>>,
+{
+    pub fn new(chunks: I) -> Self {
+        Validate {
+            chunks,
+            finished: false,
+            line: 0,
+            offset: 0,
+            partial: false,
+            line_start: 0,
+            line_len: 0,
+            last_newline: true,
+            violations: ErrorTracker::new(ErrorPolicy::FailFast),
+            final_newline: true,
+            max_line: None,
+            nul: true,
+            utf8: true,
+        }
+    }
+
+    /// Require the input to end with `\n`.
+    pub fn final_newline(&mut self, check: bool) {
+        self.final_newline = check;
+    }
+
+    /// Maximum line length, not including the `\n`.
+    pub fn max_line(&mut self, size: usize) {
+        self.max_line = Some(size);
+    }
+
+    /// Reject lines containing NUL bytes.
+    pub fn nul(&mut self, check: bool) {
+        self.nul = check;
+    }
+
+    /// Reject lines which aren't valid UTF-8.
+    pub fn utf8(&mut self, check: bool) {
+        self.utf8 = check;
+    }
+
+    /// How to handle violations.
+    pub fn error_policy(&mut self, policy: ErrorPolicy) {
+        self.violations.policy = policy;
+    }
+
+    /// Violations tolerated so far.
+    pub fn report(&self) -> &ErrorReport<Violation> {
+        &self.violations.report
+    }
+
+    /// Consume the validator, returning the violations tolerated.
+    pub fn into_report(self) -> ErrorReport<Violation> {
+        self.violations.report
+    }
+
+    /// Check every line of `chunk`, returning a violation which isn't
+    /// tolerated.
+    fn check(&mut self, chunk: &[u8]) -> Result<(), Violation> {
+        // Most chunks are clean, so validate UTF-8 over the whole chunk up front
+        // and only do it per-line if that fails.
+        let utf8 = self.utf8 && !utf8::is_valid(chunk);
+
+        for span in splitter::spans(chunk) {
+            // This is all of the line, or the part of it in this chunk.
+            let line = &chunk[span.start..span.eol];
+            let start = self.offset + span.start as u64;
+
+            if !self.partial {
+                self.line += 1;
+                self.line_start = start;
+                self.line_len = 0;
+            }
+            self.line_len += line.len();
+
+            if self.nul {
+                if let Some(nul) = memchr::memchr(0, line) {
+                    self.violation(start + nul as u64, ViolationKind::Nul)?;
+                }
+            }
+            if utf8 {
+                if let Err(err) = str::from_utf8(line) {
+                    let offset = start + err.valid_up_to() as u64;
+                    self.violation(offset, ViolationKind::InvalidUtf8)?;
+                }
+            }
+
+            // Without a `\n`, the line may carry on in the next chunk.
+            self.partial = span.eol == span.next;
+            if !self.partial {
+                self.end_line()?;
+            }
+        }
+
+        self.offset += chunk.len() as u64;
+        if let Some(&last) = chunk.last() {
+            self.last_newline = last == b'\n';
+        }
+        Ok(())
+    }
+
+    /// Check the length of the line which just ended.
+    fn end_line(&mut self) -> Result<(), Violation> {
+        self.partial = false;
+        match self.max_line {
+            Some(max_line) if self.line_len > max_line => {
+                let kind = ViolationKind::LineTooLong(self.line_len);
+                self.violation(self.line_start, kind)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the end of the input.
+    fn finish(&mut self) -> Result<(), Violation> {
+        if self.partial {
+            self.end_line()?;
+        }
+        if self.final_newline && !self.last_newline {
+            self.violation(self.offset, ViolationKind::MissingFinalNewline)?;
+        }
+        Ok(())
+    }
+
+    fn violation(&mut self, offset: u64, kind: ViolationKind) -> Result<(), Violation> {
+        let violation = Violation {
+            line: self.line,
+            offset,
+            kind,
+        };
+        match self.violations.tolerate(violation) {
+            Some(violation) => Err(violation),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I> Iterator for Validate<I>
+where
+    I: Iterator<Item = io::Result<Vec<u8>>>,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let chunk = match self.chunks.next() {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(err)) => {
+                self.finished = true;
+                return Some(Err(err));
+            }
+            None => {
+                self.finished = true;
+                let violation = self.finish().err()?;
+                return Some(Err(io::Error::new(ErrorKind::InvalidData, violation)));
+            }
+        };
+
+        match self.check(&chunk) {
+            Ok(()) => Some(Ok(chunk)),
+            Err(violation) => {
+                self.finished = true;
+                Some(Err(io::Error::new(ErrorKind::InvalidData, violation)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{LineChunks, OversizePolicy};
+
+    #[test]
+    fn test_clean() {
+        let mut validate = Validate::new(LineChunks::new(16, &b"one\ntwo\nthree\n"[..]));
+
+        assert!(validate.all(|chunk| chunk.is_ok()));
+        assert!(validate.report().is_empty());
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let input = &b"one\ntwo\nth\0ree\nfour\n"[..];
+        let mut validate = Validate::new(LineChunks::new(4, input));
+
+        let err = validate.find_map(|chunk| chunk.err()).unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let violation = err.into_inner().unwrap().downcast::<Violation>().unwrap();
+        assert_eq!(
+            *violation,
+            Violation {
+                line: 3,
+                offset: 10,
+                kind: ViolationKind::Nul
+            }
+        );
+        assert!(validate.next().is_none());
+    }
+
+    #[test]
+    fn test_report() {
+        let input = &b"one\nlonger line\n\xff\nlast"[..];
+        let mut validate = Validate::new(LineChunks::new(8, input));
+        validate.error_policy(ErrorPolicy::CollectUpTo(usize::MAX));
+        validate.max_line(8);
+
+        let data: Vec<u8> = validate.by_ref().flat_map(Result::unwrap).collect();
+        assert_eq!(data, input);
+        assert_eq!(
+            validate.into_report().errors,
+            vec![
+                Violation {
+                    line: 2,
+                    offset: 4,
+                    kind: ViolationKind::LineTooLong(11)
+                },
+                Violation {
+                    line: 3,
+                    offset: 16,
+                    kind: ViolationKind::InvalidUtf8
+                },
+                Violation {
+                    line: 4,
+                    offset: 22,
+                    kind: ViolationKind::MissingFinalNewline
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_line() {
+        let input = &b"short\nthis line is rather long\nend\n"[..];
+        let mut chunks = LineChunks::new(8, input);
+        chunks.max_line(8);
+        chunks.oversize_policy(OversizePolicy::SplitMidLine);
+        let mut validate = Validate::new(chunks);
+        validate.error_policy(ErrorPolicy::CollectUpTo(usize::MAX));
+        validate.max_line(16);
+        validate.nul(false);
+
+        let chunks: Vec<Vec<u8>> = validate.by_ref().map(Result::unwrap).collect();
+        assert!(chunks.len() > 3, "{chunks:?}");
+        assert_eq!(chunks.concat(), input);
+        // One over-long line 2, and no missing newline.
+        assert_eq!(
+            validate.into_report().errors,
+            vec![Violation {
+                line: 2,
+                offset: 6,
+                kind: ViolationKind::LineTooLong(24)
+            }]
+        );
+
+        let mut chunks = LineChunks::new(8, &b"a\nbcdefghijklmn\0"[..]);
+        chunks.max_line(4);
+        chunks.oversize_policy(OversizePolicy::SplitMidLine);
+        let mut validate = Validate::new(chunks);
+        validate.error_policy(ErrorPolicy::CollectUpTo(usize::MAX));
+        validate.by_ref().for_each(drop);
+        let lines: Vec<_> = validate
+            .into_report()
+            .errors
+            .iter()
+            .map(|violation| (violation.line, violation.offset, violation.kind.clone()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (2, 15, ViolationKind::Nul),
+                (2, 16, ViolationKind::MissingFinalNewline)
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use crate::lines::ChunkLines;
+
+/// A closed time window yielded by [`Windows`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Window {
+    /// Start time of the window. The window covers `start..start + width`.
+    pub start: u64,
+    /// Lines in the window, without their `\n`, in arrival order.
+    pub lines: Vec<Vec<u8>>,
+}
+
+/// Group lines into fixed-width time windows.
+///
+/// `timestamp` extracts a time from each non-empty line, in whatever units
+/// `width` and `lateness` are given in. Lines are grouped into windows aligned
+/// to multiples of `width`, and each window is yielded once it closes.
+///
+/// Lines may arrive out of order by up to `lateness`: a window closes once a
+/// line has been seen with a timestamp at least `lateness` past the window's
+/// end. Lines which arrive after their window has been yielded are dropped,
+/// as are lines without a timestamp; both are counted. Remaining windows are
+/// yielded in order at the end of the input. Errors are passed through.
+pub struct Windows<I, F> {
+    lines: ChunkLines<I>,
+    timestamp: F,
+    width: u64,
+    lateness: u64,
+    finished: bool,
+    open: BTreeMap<u64, Vec<Vec<u8>>>,
+    // Windows starting before this have been yielded.
+    closed: u64,
+    latest: u64,
+    late: u64,
+    untimed: u64,
+}
+
+impl<I, F, E> Windows<I, F>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+    F: FnMut(&[u8]) -> Option<u64>,
+{
+    /// Panics if `width` is 0.
+    pub fn new(chunks: I, width: u64, lateness: u64, timestamp: F) -> Self {
+        assert!(width > 0, "window width must be non-zero");
+
+        Windows {
+            lines: ChunkLines::new(chunks),
+            timestamp,
+            width,
+            lateness,
+            finished: false,
+            open: BTreeMap::new(),
+            closed: 0,
+            latest: 0,
+            late: 0,
+            untimed: 0,
+        }
+    }
+
+    /// Number of lines dropped because their window had already closed.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    /// Number of lines dropped because they had no timestamp.
+    pub fn untimed(&self) -> u64 {
+        self.untimed
+    }
+
+    /// Pop the earliest window if it's closed (or all remaining windows are
+    /// once the input is finished).
+    fn pop_closed(&mut self) -> Option<Window> {
+        let watermark = self.latest.saturating_sub(self.lateness);
+        let entry = self.open.first_entry()?;
+
+        if !self.finished && entry.key().saturating_add(self.width) > watermark {
+            return None;
+        }
+
+        let start = *entry.key();
+        self.closed = start.saturating_add(self.width);
+        Some(Window {
+            start,
+            lines: entry.remove(),
+        })
+    }
+
+    fn add(&mut self, line: Vec<u8>) {
+        let Some(time) = (self.timestamp)(&line) else {
+            self.untimed += 1;
+            return;
+        };
+        let start = time - time % self.width;
+
+        if start < self.closed {
+            self.late += 1;
+        } else {
+            self.open.entry(start).or_default().push(line);
+            self.latest = self.latest.max(time);
+        }
+    }
+}
+
+impl<I, F, E> Iterator for Windows<I, F>
+where
+    I: Iterator<Item = Result<Vec<u8>, E>>,
+    F: FnMut(&[u8]) -> Option<u64>,
+{
+    type Item = Result<Window, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(window) = self.pop_closed() {
+                break Some(Ok(window));
+            }
+            if self.finished {
+                break None;
+            }
+
+            match self.lines.next() {
+                Some(Ok(line)) => self.add(line),
+                Some(Err(err)) => break Some(Err(err)),
+                None => self.finished = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineChunks;
+
+    fn time(line: &[u8]) -> Option<u64> {
+        let field = line.split(|&c| c == b' ').next()?;
+        std::str::from_utf8(field).ok()?.parse().ok()
+    }
+
+    #[test]
+    fn test_windows() {
+        let input = &b"0 a\n30 b\n65 c\n50 d\n130 e\n10 f\nbogus\n200 g\n"[..];
+        let mut windows = Windows::new(LineChunks::new(8, input), 60, 20, time);
+
+        let got: Vec<_> = windows
+            .by_ref()
+            .map(|window| {
+                let window = window.unwrap();
+                let lines: Vec<_> = window
+                    .lines
+                    .iter()
+                    .map(|line| String::from_utf8_lossy(line).into_owned())
+                    .collect();
+                (window.start, lines)
+            })
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                (
+                    0,
+                    vec!["0 a".to_string(), "30 b".to_string(), "50 d".to_string()]
+                ),
+                (60, vec!["65 c".to_string()]),
+                (120, vec!["130 e".to_string()]),
+                (180, vec!["200 g".to_string()]),
+            ]
+        );
+        assert_eq!(windows.late(), 1);
+        assert_eq!(windows.untimed(), 1);
+    }
+}